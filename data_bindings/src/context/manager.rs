@@ -52,12 +52,12 @@ impl<G, V> ContextManager<G, V>
     }
 
     /// Returns the store associated with the given view name.
-    pub fn get_view_store<'a>(&'a self, view_name: &String) -> Option<&'a V> {
+    pub fn get_view_store<'a>(&'a self, view_name: &str) -> Option<&'a V> {
         self.views.get(view_name)
     }
 
     /// Returns the store associated with the given view name with mutable access.
-    pub fn get_view_store_mut<'a>(&'a mut self, view_name: &String) -> Option<&'a mut V> {
+    pub fn get_view_store_mut<'a>(&'a mut self, view_name: &str) -> Option<&'a mut V> {
         self.views.get_mut(view_name)
     }
 
@@ -149,14 +149,14 @@ impl<G, V> DataBindingsContext for ContextManager<G, V>
     where G: Store + Default,
           V: Store
 {
-    fn get_view_context<'a>(&'a self, view: &String) -> ViewContext<'a> {
+    fn get_view_context<'a>(&'a self, view: &str) -> ViewContext<'a> {
         ViewContext {
             view_context: self.get_view_store(view).map(|vs| vs as &Store),
             global: &self.global as &Store
         }
     }
 
-    fn get_view_context_mut<'a>(&'a mut self, view: &String) -> ViewContextMut<'a> {
+    fn get_view_context_mut<'a>(&'a mut self, view: &str) -> ViewContextMut<'a> {
         ViewContextMut {
             view_context: self.views.get_mut(view).map(|vs| vs as &mut Store),
             global: &mut self.global as &mut Store
@@ -191,6 +191,78 @@ impl<'a> ViewContext<'a> {
             None
         }
     }
+
+    /// Returns an iterator over the items of the collection
+    /// found at `property_path`.
+    pub fn get_iterable(&'a self, property_path: &str) -> Option<Box<Iterator<Item=&'a Store> + 'a>> {
+        match self.view_context {
+            Some(ref store) =>
+                if let AttributeGetResult::IterableType(it) = store
+                    .get_attribute(PropertyAccessor::new(property_path)) {
+                    return Some(it);
+                },
+            _ => ()
+        }
+        if let AttributeGetResult::IterableType(it) = self.global
+            .get_attribute(PropertyAccessor::new(property_path)) {
+            Some(it)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of items of the collection found at `property_path`.
+    /// The collection is only walked if its iterator doesn't know its length.
+    pub fn get_iterable_len(&'a self, property_path: &str) -> Option<usize> {
+        self.get_iterable(property_path).map(|it| {
+            match it.size_hint() {
+                (lower, Some(upper)) if lower == upper => lower,
+                _ => it.count(),
+            }
+        })
+    }
+
+    /// Returns at most `count` items of the collection found at
+    /// `property_path`, from the item `first`. The items before
+    /// `first` are skipped with `nth`, without being walked when
+    /// the collection supports it. Returns an empty list if there
+    /// is no such collection.
+    pub fn get_items_in(&'a self, property_path: &str, first: usize, count: usize)
+        -> Vec<&'a Store>
+    {
+        let mut items = Vec::with_capacity(count);
+        if count > 0 {
+            if let Some(mut it) = self.get_iterable(property_path) {
+                if let Some(item) = it.nth(first) {
+                    items.push(item);
+                    items.extend(it.take(count - 1));
+                }
+            }
+        }
+        items
+    }
+
+    /// Returns the value of `property_path` for `item`, an item
+    /// of a collection returned by `get_items_in`.
+    ///
+    /// The lookup order is: the item, then the view store and the
+    /// global store as in `get_attribute`. So a template can show
+    /// values that don't belong to its item, but a property missing
+    /// from the item silently resolves to a global key of the same
+    /// name, if there is one.
+    pub fn get_item_attribute(
+        &'a self,
+        item: Option<&'a Store>,
+        property_path: &str) -> Option<StoreValue<'a>>
+    {
+        if let Some(item) = item {
+            if let AttributeGetResult::PrimitiveType(sv) = item
+                .get_attribute(PropertyAccessor::new(property_path)) {
+                return Some(sv);
+            }
+        }
+        self.get_attribute(property_path)
+    }
 }
 
 impl<'a> ViewContextMut<'a> {
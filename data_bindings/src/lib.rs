@@ -177,9 +177,9 @@ pub enum AttributeSetResult<'a> {
 pub trait DataBindingsContext {
     /// Returns a context for the given view that support lookup on `Store` registered in
     /// the `ContextManager`.
-    fn get_view_context<'a>(&'a self, view_name: &String) -> self::context::ViewContext<'a>;
+    fn get_view_context<'a>(&'a self, view_name: &str) -> self::context::ViewContext<'a>;
     /// Identical to `get_view_context` except that you have a mutable access to the context.
-    fn get_view_context_mut<'a>(&'a mut self, view_name: &String) -> self::context::ViewContextMut<'a>;
+    fn get_view_context_mut<'a>(&'a mut self, view_name: &str) -> self::context::ViewContextMut<'a>;
 }
 
 
@@ -295,4 +295,18 @@ mod test {
         assert_eq!(context.get_attribute("main", "player.pv").unwrap(), StoreValue::Integer(42));
     }
 
+    #[test]
+    fn items_in_should_start_at_the_first_item() {
+        let mut context = DefaultContextManager::default();
+        let players: Vec<_> = (0..5).map(|i| Player::new("Grub", i, 0)).collect();
+        context.register_global_store("players".to_string(), players);
+
+        let view = context.get_view_context("main");
+        let items = view.get_items_in("players", 3, 5);
+        assert_eq!(items.len(), 2);
+        assert_eq!(view.get_item_attribute(Some(items[0]), "pv").unwrap(), StoreValue::Integer(3));
+        assert!(view.get_items_in("players", 5, 2).is_empty());
+        assert!(view.get_items_in("players", 0, 0).is_empty());
+    }
+
 }
@@ -80,6 +80,14 @@ impl<'a, T> Iterator for WrapperIter<'a, T>
     fn next(&mut self) -> Option<Self::Item> {
         self.it.next().map(|i| i as &Store)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.it.nth(n).map(|i| i as &Store)
+    }
 }
 
 impl<'a, T> Iterator for WrapperIterMut<'a, T>
@@ -90,6 +98,14 @@ impl<'a, T> Iterator for WrapperIterMut<'a, T>
     fn next(&mut self) -> Option<Self::Item> {
         self.it.next().map(|i| i as &mut Store)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.it.nth(n).map(|i| i as &mut Store)
+    }
 }
 /*impl<'a, T> From<&'a [T]> for StoreValue<'a>
     where T: Store
@@ -1,6 +1,7 @@
 
 pub use self::cast::Cast;
 pub use self::cast::AssignFromCast;
+pub use self::cast::AsStoreValue;
 pub use self::value::StoreValue;
 pub use self::value::StoreValueStatic;
 
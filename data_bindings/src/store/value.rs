@@ -1,3 +1,5 @@
+use std::fmt;
+
 /// `StoreValue` is the type that encapsulate
 /// a value extracted from a Store
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -14,7 +16,7 @@ pub enum StoreValue<'a> {
 ///     ...
 /// }
 /// ```
-#[derive(Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum StoreValueStatic {
     String(String),
     Integer(i64),
@@ -51,3 +53,23 @@ impl From<bool> for StoreValueStatic {
         StoreValueStatic::Boolean(b)
     }
 }
+
+impl<'a> fmt::Display for StoreValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoreValue::String(s) => write!(f, "{}", s),
+            StoreValue::Integer(i) => write!(f, "{}", i),
+            StoreValue::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl fmt::Display for StoreValueStatic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoreValueStatic::String(ref s) => write!(f, "{}", s),
+            StoreValueStatic::Integer(i) => write!(f, "{}", i),
+            StoreValueStatic::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
@@ -1,7 +1,4 @@
 
-use std::io::BufReader;
-use std::fs::File;
-use std::path::Path;
 use std::thread;
 
 use glium::DisplayBuild;
@@ -15,11 +12,7 @@ pub fn run_example(title: &str, markup_path: &str, deps_path: &str, style_path:
     //////////////////////////////////////////////////////////////////////////////
     // oil related code
     //
-    let mut library = {
-        let file = File::open(&Path::new(markup_path)).unwrap();
-        let reader = BufReader::new(file);
-        oil::markup::parse(oil::StdOutErrorReporter, reader)
-    };
+    let mut library = oil::markup::parse_file(oil::StdOutErrorReporter, markup_path);
 
     library.resolve_templates();
 
@@ -40,17 +33,13 @@ pub fn run_example(title: &str, markup_path: &str, deps_path: &str, style_path:
 
     let mut resource_manager = oil::resource::create_resource_manager(&display);
 
-    let stylesheet = {
-        let file = File::open(&Path::new(style_path)).unwrap();
-        let reader = BufReader::new(file);
-        oil::style::parse(oil::StdOutErrorReporter, reader, &defs, &mut resource_manager)
-    };
+    let stylesheet = oil::style::parse_file(
+        oil::StdOutErrorReporter, style_path, &defs, &mut resource_manager);
 
     let (width, height) = display.get_window().unwrap().get_inner_size().unwrap();
 
     let mut renderer = oil::rendering::backend::GliumRenderer::new(&display);
     let mut router = oil::Router::from_library_and_stylesheet(
-        &display,
         &resource_manager,
         library,
         &stylesheet
@@ -65,7 +54,7 @@ pub fn run_example(title: &str, markup_path: &str, deps_path: &str, style_path:
         let vp = oil::Viewport { width: width as f32, height: height as f32 };
 
         // Update views
-        router.update(&display, &resource_manager, vp, &mut data_binder_context);
+        router.update(&resource_manager, vp, &mut data_binder_context);
 
         // Render views
         let mut f = renderer.prepare_frame(vp);
@@ -89,6 +78,7 @@ pub fn run_example(title: &str, markup_path: &str, deps_path: &str, style_path:
                         _ => ()
                     }
                 }
+                glutin::Event::MouseWheel(delta) => router.scroll(0.0, -delta as f32),
                 _ => ()
             }
         }
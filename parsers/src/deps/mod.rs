@@ -32,7 +32,8 @@ pub fn parse<E, B>(reporter: E, reader: B) -> StyleDefinitions
     let mut parser = parser::Parser::new(
         reporter,
         reader,
-        Path::new(".").to_path_buf()
+        Path::new(".").to_path_buf(),
+        None
     );
     parser.parse()
 }
@@ -54,7 +55,8 @@ pub fn parse_file<E, P>(reporter: E, path: P) -> StyleDefinitions
     let mut parser = parser::Parser::new(
         reporter,
         reader,
-        path.as_ref().parent().unwrap_or(Path::new(".")).to_path_buf()
+        path.as_ref().parent().unwrap_or(Path::new(".")).to_path_buf(),
+        Some(path.as_ref().display().to_string())
     );
     parser.parse()
 }
@@ -5,7 +5,10 @@ use std::path::{PathBuf, Path};
 use report::ErrorReporter;
 use parsing::BufferConsumer;
 use parsing::Error;
+use diagnostic::ErrorCode;
 
+use oil_shared::asset::SliceInsets;
+use oil_shared::color::Color;
 use oil_shared::deps::Constructor;
 use oil_shared::deps::StyleDefinitions;
 
@@ -14,6 +17,7 @@ pub struct Parser<E, B> {
     bc: BufferConsumer<B>,
     prefix: String,
     relative_to: PathBuf,
+    file_name: Option<String>,
 }
 
 
@@ -22,12 +26,18 @@ impl<E, B> Parser<E, B>
           B: BufRead
 {
 
-    pub fn new(reporter: E, reader: B, folder_parent: PathBuf) -> Parser<E, B> {
+    pub fn new(
+        reporter: E,
+        reader: B,
+        folder_parent: PathBuf,
+        file_name: Option<String>) -> Parser<E, B>
+    {
         Parser {
             err: reporter,
             bc: BufferConsumer::new(reader),
             prefix: "".to_string(),
             relative_to: folder_parent,
+            file_name: file_name,
         }
     }
 
@@ -49,8 +59,8 @@ impl<E, B> Parser<E, B>
                 Some('[')  => match self.parse_prefix() {
                     Ok(prefix) => self.prefix = prefix,
                     Err(err) => {
-                        self.err.log(format!("Error {}", err));
-                        break 'def;
+                        self.err.report(err.into_diagnostic(self.file_name.clone()));
+                        self.skip_definition();
                     }
                 },
                 _          => match self.parse_def() {
@@ -64,8 +74,8 @@ impl<E, B> Parser<E, B>
                         }
                     }
                     Err(err) => {
-                        self.err.log(format!("Error {}", err));
-                        break 'def;
+                        self.err.report(err.into_diagnostic(self.file_name.clone()));
+                        self.skip_definition();
                     }
                 }
             }
@@ -84,6 +94,35 @@ impl<E, B> Parser<E, B>
         Ok(prefix)
     }
 
+    /// Skip everything until the next line starting (with no indentation)
+    /// by a `[section]` or a definition name.
+    ///
+    /// The arguments of a constructor are expected to be indented,
+    /// otherwise the parser will resume in the middle of them.
+    fn skip_definition(&mut self) {
+        // The error might have been detected on the first
+        // character of the next definition.
+        if self.bc.position().1 == 0 {
+            match self.bc.look_next_char() {
+                Some(c) if c == '[' || c.is_alphabetic() => return,
+                _ => ()
+            }
+        }
+
+        loop {
+            match self.bc.consume_any_char() {
+                Some('\n') => {
+                    match self.bc.look_next_char() {
+                        Some(c) if c == '[' || c.is_alphabetic() => return,
+                        _ => ()
+                    }
+                }
+                Some(_) => (),
+                None => return
+            }
+        }
+    }
+
     fn parse_def(&mut self) -> Result<(String, Constructor), Error> {
         let name = try!(self.bc.consume_path());
         try!(self.bc.consume_whitespace());
@@ -125,7 +164,7 @@ impl<E, B> Parser<E, B>
                 let path = try!(self.find_str_arg(args.iter(), "path", 0));
                 let width = try!(self.find_num_arg(args.iter(), "width", 0));
                 let height = try!(self.find_num_arg(args.iter(), "height", 1));
-                Ok(Constructor::Font(path, width, height))
+                Ok(Constructor::Font(self.resolve_path(path), width, height))
             },
             "Image" => {
                 let path = try!(self.find_str_arg(args.iter(), "path", 0));
@@ -133,12 +172,52 @@ impl<E, B> Parser<E, B>
                 let height = self.find_num_arg(args.iter(), "height", 1).ok();
                 let offset_x = self.find_num_arg(args.iter(), "offset-x", 2).ok();
                 let offset_y = self.find_num_arg(args.iter(), "offset-y", 3).ok();
-                Ok(Constructor::Image(self.resolve_path(path), width, height, offset_x, offset_y))
+                // `slice` gives the same inset to every side,
+                // it can be overridden side by side.
+                let slice = self.find_num_arg(args.iter(), "slice", 4).ok();
+                let left = self.find_num_arg(args.iter(), "slice-left", 5).ok();
+                let right = self.find_num_arg(args.iter(), "slice-right", 6).ok();
+                let top = self.find_num_arg(args.iter(), "slice-top", 7).ok();
+                let bottom = self.find_num_arg(args.iter(), "slice-bottom", 8).ok();
+                let insets = if slice.is_some() || left.is_some() || right.is_some()
+                    || top.is_some() || bottom.is_some()
+                {
+                    let s = slice.unwrap_or(0f32);
+                    Some(SliceInsets {
+                        left: left.unwrap_or(s),
+                        right: right.unwrap_or(s),
+                        top: top.unwrap_or(s),
+                        bottom: bottom.unwrap_or(s),
+                    })
+                } else {
+                    None
+                };
+                Ok(Constructor::Image(self.resolve_path(path), width, height,
+                    offset_x, offset_y, insets))
+            }
+            "Color" => {
+                // Either `Color("#rrggbbaa")` or `Color(r, g, b, a)`
+                if let Ok(hex) = self.find_str_arg(args.iter(), "hex", 0) {
+                    match Color::from_hex(hex.trim_left_matches('#')) {
+                        Some(color) => Ok(Constructor::Color(color)),
+                        None => Err(self.bc.error_code(
+                            ErrorCode::InvalidColor,
+                            format!("Invalid color: `{}`", hex)
+                        ))
+                    }
+                } else {
+                    let r = try!(self.find_num_arg(args.iter(), "r", 0));
+                    let g = try!(self.find_num_arg(args.iter(), "g", 1));
+                    let b = try!(self.find_num_arg(args.iter(), "b", 2));
+                    let a = self.find_num_arg(args.iter(), "a", 3).unwrap_or(1f32);
+                    Ok(Constructor::Color(Color::from_rgba(r, g, b, a)))
+                }
             }
             _ => {
-                Err(self.bc.error(
+                Err(self.bc.error_code(
+                    ErrorCode::UnknownConstructor,
                     "Unknown constructor. \
-                    Can be either `Image` or `Font`"
+                    Can be either `Image`, `Font` or `Color`".to_string()
                 ))
             }
         }
@@ -226,9 +305,10 @@ impl<E, B> Parser<E, B>
                             arg_type: ArgType::Number(val)
                         })
                     }
-                    _ => Err(self.bc.error(
+                    _ => Err(self.bc.error_code(
+                        ErrorCode::InvalidArgument,
                         "Unknown argument type. \
-                        Can be either `String` or `Number`"
+                        Can be either `String` or `Number`".to_string()
                     ))
                 }
             }
@@ -252,9 +332,10 @@ impl<E, B> Parser<E, B>
                 _ => false
             }
         });
+        // Named arguments don't count for the position.
         let i2 = iter.clone().filter(|&x| {
             match x.arg_type {
-                ArgType::Strstr(_) => true,
+                ArgType::Strstr(_) => x.name.is_empty(),
                 _ => false
             }
         });
@@ -279,7 +360,7 @@ impl<E, B> Parser<E, B>
         });
         let i2 = iter.clone().filter(|&x| {
             match x.arg_type {
-                ArgType::Number(_) => true,
+                ArgType::Number(_) => x.name.is_empty(),
                 _ => false
             }
         });
@@ -310,7 +391,8 @@ impl<E, B> Parser<E, B>
         if try_by_name.is_none() {
             match by_pos {
                 Some(a) => Ok(a),
-                None => Err(self.bc.error_str(
+                None => Err(self.bc.error_code(
+                    ErrorCode::MissingArgument,
                     format!("argument `{}` not found", name
                 )))
             }
@@ -332,3 +414,92 @@ struct Arg {
     pub name: String,
     pub arg_type: ArgType,
 }
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::io::BufReader;
+    use std::path::Path;
+    use oil_shared::asset::SliceInsets;
+    use oil_shared::color::Color;
+    use oil_shared::deps::Constructor;
+    use VecErrorReporter;
+    use Severity;
+
+    #[test]
+    fn invalid_definition_should_only_drop_itself() {
+        let reporter = VecErrorReporter::new();
+        let reader = BufReader::new(
+            "[btn]\n\
+             a = Foo(\n    width: 12\n)\n\
+             b = 12\n\
+             [ item\n\
+             c = \"auto\"\n".as_bytes());
+        let mut parser = super::Parser::new(
+            reporter.clone(), reader, Path::new(".").to_path_buf(), None);
+
+        let defs = parser.parse();
+
+        assert_eq!(reporter.count(Severity::Error), 2);
+        assert_eq!(defs.defs.len(), 2);
+        assert!(defs.defs.contains_key("btn.b"));
+        assert!(defs.defs.contains_key("btn.c"));
+    }
+
+    #[test]
+    fn color_should_accept_hex_or_components() {
+        let reporter = VecErrorReporter::new();
+        let reader = BufReader::new(
+            "[overlay]\n\
+             a = Color(\"#00000080\")\n\
+             b = Color(r: 255, g: 128, b: 0)\n\
+             c = Color(0, 0, 0, 0.5)\n".as_bytes());
+        let mut parser = super::Parser::new(
+            reporter.clone(), reader, Path::new(".").to_path_buf(), None);
+
+        let defs = parser.parse();
+        let color = |name: &str| match defs.defs[name] {
+            Constructor::Color(c) => c,
+            _ => panic!("Expected a color"),
+        };
+
+        assert_eq!(reporter.count(Severity::Error), 0);
+        assert_eq!(color("overlay.a"), Color::new(0, 0, 0, 128));
+        assert_eq!(color("overlay.b"), Color::new(255, 128, 0, 255));
+        assert_eq!(color("overlay.c"), Color::new(0, 0, 0, 128));
+    }
+
+    #[test]
+    fn image_slice_should_be_overridable_per_side() {
+        let reporter = VecErrorReporter::new();
+        let reader = BufReader::new(
+            "a = Image(path: \"btn.png\", slice: 8, slice-top: 4)\n\
+             b = Image(path: \"btn.png\")\n".as_bytes());
+        let mut parser = super::Parser::new(
+            reporter.clone(), reader, Path::new(".").to_path_buf(), None);
+
+        let defs = parser.parse();
+
+        assert_eq!(reporter.count(Severity::Error), 0);
+        match defs.defs["a"] {
+            Constructor::Image(_, width, _, _, _, slice) => {
+                assert_eq!(width, None);
+                assert_eq!(slice, Some(SliceInsets {
+                    left: 8.0,
+                    right: 8.0,
+                    top: 4.0,
+                    bottom: 8.0,
+                }));
+            }
+            _ => panic!("Expected an image"),
+        }
+        match defs.defs["b"] {
+            Constructor::Image(_, _, _, _, _, slice) => assert_eq!(slice, None),
+            _ => panic!("Expected an image"),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+/// Severity of a `Diagnostic`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// Invalid source: the part containing it (a rule, a declaration...)
+    /// is missing from the result. The parser resumes after it.
+    Error,
+    /// Something has been ignored, but the parser went on.
+    Warning,
+}
+
+/// Kind of problem reported by a `Diagnostic`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    // Common to the style and deps parsers.
+    InvalidSyntax,
+    UnexpectedChar,
+    UnexpectedEof,
+    InvalidNumber,
+    InvalidColor,
+
+    // Markup.
+    XmlError,
+    UnknownTag,
+    InvalidRootTag,
+    IgnoredTag,
+    UnnamedTemplate,
+    MissingAttribute,
+    InvalidDataBinding,
+    TemplateNotFound,
+
+    // Style.
+    UnknownSelectorState,
+    UnknownKeyword,
+    UnknownUnit,
+    InvalidUnit,
+    UnexpectedList,
+    UnknownDefinition,
+    ResourceLoading,
+
+    // Deps.
+    UnknownConstructor,
+    InvalidArgument,
+    MissingArgument,
+}
+
+/// Position in a source, row and col start at 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Part of the source a `Diagnostic` refers to.
+/// When only the start is known, `end` is equal to `start`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+
+    /// Create a span that refers to a single position.
+    pub fn at(row: usize, col: usize) -> Span {
+        let pos = Position { row: row, col: col };
+        Span { start: pos, end: pos }
+    }
+}
+
+/// A problem found while parsing one of the oil languages.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    /// Source file, if the parser was given one.
+    pub file: Option<String>,
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl Diagnostic {
+
+    pub fn new(severity: Severity, code: ErrorCode, message: String) -> Diagnostic {
+        Diagnostic {
+            severity: severity,
+            code: code,
+            file: None,
+            span: None,
+            message: message,
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_file(mut self, file: Option<String>) -> Diagnostic {
+        self.file = file;
+        self
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "Error"),
+            Severity::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}", self.severity));
+        match (&self.file, self.span) {
+            (&Some(ref file), Some(span)) => try!(write!(f, " {}:{}:{}",
+                file, span.start.row + 1, span.start.col + 1)),
+            (&Some(ref file), None) => try!(write!(f, " {}", file)),
+            (&None, Some(span)) => try!(write!(f, " {}:{}",
+                span.start.row + 1, span.start.col + 1)),
+            (&None, None) => (),
+        }
+        write!(f, " : {}", self.message)
+    }
+}
@@ -16,6 +16,9 @@ pub mod deps;
 pub use self::report::ErrorReporter;
 pub use self::report::StdOutErrorReporter;
 pub use self::report::EmptyErrorReporter;
+pub use self::report::VecErrorReporter;
+pub use self::diagnostic::{Diagnostic, Severity, ErrorCode, Span, Position};
 
 mod parsing;
 mod report;
+mod diagnostic;
@@ -5,6 +5,7 @@ use oil_shared::markup::{
     Node, NodeType, TemplateData, View, Template
 };
 use ErrorReporter;
+use diagnostic::{Diagnostic, Severity, ErrorCode};
 
 // Library
 pub struct Library<E> {
@@ -73,8 +74,10 @@ impl<E> Library<E>
             NodeType::Template(TemplateData { ref path }) => {
                 match templates.get(path) {
                     None => {
-                        err.log(format!(
-                                "Warning `{}` template name not found", path));
+                        err.report(Diagnostic::new(
+                            Severity::Warning,
+                            ErrorCode::TemplateNotFound,
+                            format!("`{}` template name not found", path)));
                         None
                     }
                     Some(found) => Some(Node::from_template(found, NodeType::Group)),
@@ -2,8 +2,11 @@
 // Dependencies
 use self::parser::Parser;
 use xml::attribute::OwnedAttribute;
-use std::io::BufRead;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::fs::File;
 use ErrorReporter;
+use diagnostic::ErrorCode;
 use oil_shared::markup::Node;
 
 pub use self::lib::Library;
@@ -46,6 +49,27 @@ pub fn parse<E, B>(reporter: E, reader: B) -> Library<E>
     parser.parse()
 }
 
+/// Parse the given markup file.
+///
+/// This function is equivalent to `parse()` except that
+/// diagnostics are prefixed by the file path.
+///
+/// ## Panics
+///
+/// This function panics if the file can't be found.
+pub fn parse_file<E, P>(reporter: E, path: P) -> Library<E>
+    where E: ErrorReporter,
+          P: AsRef<Path>
+{
+    let reader = BufReader::new(File::open(path.as_ref()).unwrap());
+    let mut parser = Parser::with_file_name(
+        reporter,
+        reader,
+        path.as_ref().display().to_string()
+    );
+    parser.parse()
+}
+
 
 trait HasNodeChildren {
     fn add(&mut self, maybe_child: Option<Node>);
@@ -66,7 +90,7 @@ fn lookup_name<'a>(name: &'a str,
 }
 
 enum ErrorStatus {
-    NotReported(&'static str),
+    NotReported(ErrorCode, &'static str),
     Reported,
 }
 
@@ -2,10 +2,13 @@
 use xml::reader::EventReader;
 use xml::reader::events::*;
 use xml::attribute::OwnedAttribute;
+use xml::common::Position;
 use std::io::Read;
 
 use std::collections::HashMap;
+use xml::common::Error as XmlError;
 use ErrorReporter;
+use diagnostic::{Diagnostic, Severity, ErrorCode, Span};
 use oil_shared;
 use parsing::BufferConsumer;
 
@@ -37,6 +40,7 @@ use oil_shared::markup::{
 pub struct Parser<E, B: Read> {
     err: E,
     parser: EventReader<B>,
+    file_name: Option<String>,
 }
 
 impl<E, B> Parser<E, B>
@@ -47,7 +51,18 @@ impl<E, B> Parser<E, B>
     pub fn new(reporter: E, reader: B) -> Parser<E, B> {
         Parser {
             err: reporter,
-            parser: EventReader::new(reader)
+            parser: EventReader::new(reader),
+            file_name: None,
+        }
+    }
+
+    /// Same as `new` except that diagnostics will
+    /// be prefixed by the given file name.
+    pub fn with_file_name(reporter: E, reader: B, file_name: String) -> Parser<E, B> {
+        Parser {
+            err: reporter,
+            parser: EventReader::new(reader),
+            file_name: Some(file_name),
         }
     }
 
@@ -74,7 +89,7 @@ impl<E, B> Parser<E, B>
                     }
                 }
                 XmlEvent::Error(e) => {
-                    self.err.log(format!("Error: {}", e));
+                    self.report_xml_error(&e);
                     break 'doc;
                 }
                 XmlEvent::EndDocument => break 'doc,
@@ -114,14 +129,10 @@ impl<E, B> Parser<E, B>
 
                 match attr_name {
                     None => {
-                        // FIXME(Waiting the pull request on netvl/xml-rs to be merged)
-                        // let (row, col) = self.parser.get_cursor();
-                        let (row, col) = (0, 0);
-                        self.err.log(
-                            format!(
-                                "Warning {}:{} : `template` has no name add an \
-                                 attribute 'name=\"<a-name>\"'",
-                            row, col)
+                        self.warning(
+                            ErrorCode::UnnamedTemplate,
+                            "`template` has no name add an \
+                             attribute 'name=\"<a-name>\"'".to_string()
                         );
 
                         self.consume_children(name)
@@ -141,14 +152,12 @@ impl<E, B> Parser<E, B>
                 Ok(())
             }
             _ => {
-                // FIXME(Waiting the pull request on netvl/xml-rs to be merged)
-                // let (row, col) = self.parser.get_cursor();
-                let (row, col) = (0, 0);
-                self.err.log(
+                self.warning(
+                    ErrorCode::InvalidRootTag,
                     format!(
-                        "Warning {}:{} : `{}` can't be at root level, \
+                        "`{}` can't be at root level, \
                         you can only have `template` or `view`"
-                    , row+1, col+1, name));
+                    , name));
 
                 self.consume_children(name)
             }
@@ -170,11 +179,9 @@ impl<E, B> Parser<E, B>
             PROGRESS_BAR_TAG => tags::parse_pbar(attributes),
             REPEAT_TAG       => tags::parse_repeat(attributes),
             _ => {
-                // FIXME(Waiting the pull request on netvl/xml-rs to be merged)
-                // let (row, col) = self.parser.get_cursor();
-                let (row, col) = (0, 0);
-                self.err.log(
-                    format!("Warning {}:{} : Unknown tag `{}`", row+1, col+1, name)
+                self.warning(
+                    ErrorCode::UnknownTag,
+                    format!("Unknown tag `{}`", name)
                 );
                 Err((ErrorType::Warning, ErrorStatus::Reported))
             }
@@ -214,10 +221,13 @@ impl<E, B> Parser<E, B>
         }
     }
 
-    fn parse_data_binding(&mut self, text: &str, parent: &mut Node) {
+    /// Parse the text content of a node. `start` is the position (row, col)
+    /// of the text in the document, used to locate the data binding errors.
+    fn parse_data_binding(&mut self, text: &str, start: (usize, usize), parent: &mut Node) {
         let mut buf_consumer = BufferConsumer::new(text.as_bytes());
         loop {
             let text = buf_consumer.consume_while(|c| c != '{').unwrap();
+            let binding_start = text_span(start, buf_consumer.position());
             if !text.is_empty() {
                 parent.add(Some(Node::new(
                             None,
@@ -238,8 +248,8 @@ impl<E, B> Parser<E, B>
                                                         NodeType::Binding(data_binding))));
                                         }
                                         _ => {
-                                            let (row, col) = (0, 0);
-                                            self.err.log(format!("Warning {}:{} : Missing '}}' for data binding", row, col));
+                                            self.report(Severity::Warning, ErrorCode::InvalidDataBinding, binding_start,
+                                                "Missing '}' for data binding".to_string());
                                             data_binding.insert(0, '{');
                                             data_binding.insert(0, '{');
                                             data_binding.push('}');
@@ -251,8 +261,8 @@ impl<E, B> Parser<E, B>
                                     }
                                 }
                                 _ => {
-                                    let (row, col) = (0, 0);
-                                    self.err.log(format!("Warning {}:{} : Missing '}}' for data binding", row, col));
+                                    self.report(Severity::Warning, ErrorCode::InvalidDataBinding, binding_start,
+                                        "Missing '}' for data binding".to_string());
                                     data_binding.insert(0, '{');
                                     data_binding.insert(0, '{');
                                     parent.add(Some(Node::new(
@@ -263,8 +273,8 @@ impl<E, B> Parser<E, B>
                             }
                         }
                         _ => {
-                            let (row, col) = (0, 0);
-                            self.err.log(format!("Warning {}:{} : Missing \"{{ .. }}}}\" for data binding", row, col));
+                            self.report(Severity::Warning, ErrorCode::InvalidDataBinding, binding_start,
+                                "Missing \"{ .. }}\" for data binding".to_string());
                             let text = "{".to_string();
                             parent.add(Some(Node::new(
                                         None,
@@ -281,20 +291,14 @@ impl<E, B> Parser<E, B>
     fn report_error_if_needed(&mut self,
                               parse_error: ParseError) -> ParseError
     {
-        // FIXME(Waiting the pull request on netvl/xml-rs to be merged)
-        // let (row, col) = self.parser.get_cursor();
-        let (row, col) = (0, 0);
         match parse_error {
-            (ErrorType::Fatal, ErrorStatus::NotReported(msg)) => {
-                self.err.log(
-                    format!("Error {}:{} : {}", row+1, col+1, msg)
-                );
+            (ErrorType::Fatal, ErrorStatus::NotReported(code, msg)) => {
+                let span = self.current_span();
+                self.report(Severity::Error, code, span, msg.to_string());
                 (ErrorType::Fatal, ErrorStatus::Reported)
             }
-            (ErrorType::Warning, ErrorStatus::NotReported(msg)) => {
-                self.err.log(
-                    format!("Warning {}:{} : {}", row+1, col+1, msg)
-                );
+            (ErrorType::Warning, ErrorStatus::NotReported(code, msg)) => {
+                self.warning(code, msg.to_string());
                 (ErrorType::Warning, ErrorStatus::Reported)
             }
             _ => parse_error
@@ -312,12 +316,9 @@ impl<E, B> Parser<E, B>
 
                     depth += 1;
 
-                    // FIXME(Waiting the pull request on netvl/xml-rs to be merged)
-                    // let (row, col) = self.parser.get_cursor();
-                    let (row, col) = (0, 0);
-                    self.err.log(
-                        format!("Warning {}:{}, `{}` has been ignored",
-                                row+1, col+1, name)
+                    self.warning(
+                        ErrorCode::IgnoredTag,
+                        format!("`{}` has been ignored", name)
                     );
                 }
                 XmlEvent::EndElement { name } => {
@@ -329,7 +330,7 @@ impl<E, B> Parser<E, B>
                 }
                 XmlEvent::Error( e ) => {
 
-                    self.err.log(format!("Error: {}", e));
+                    self.report_xml_error(&e);
                     return Err((ErrorType::Fatal, ErrorStatus::Reported));
                 }
                 _ => ()
@@ -367,11 +368,13 @@ impl<E, B> Parser<E, B>
                     return Ok(());
                 }
                 XmlEvent::Characters( text ) => {
-                    self.parse_data_binding(&text, parent);
+                    let pos = self.parser.position();
+                    let start = (pos.row as usize, pos.column as usize);
+                    self.parse_data_binding(&text, start, parent);
                 }
                 XmlEvent::Error( e ) => {
 
-                    self.err.log(format!("Error: {}", e));
+                    self.report_xml_error(&e);
                     return Err((ErrorType::Fatal, ErrorStatus::Reported));
                 }
                 XmlEvent::EndDocument => unreachable!(),
@@ -380,6 +383,48 @@ impl<E, B> Parser<E, B>
         }
     }
 
+    fn report(&self, severity: Severity, code: ErrorCode, span: Span, msg: String) {
+        self.err.report(
+            Diagnostic::new(severity, code, msg)
+                .with_span(span)
+                .with_file(self.file_name.clone())
+        );
+    }
+
+    /// Report a warning located at the last event
+    /// returned by the xml parser.
+    fn warning(&self, code: ErrorCode, msg: String) {
+        let span = self.current_span();
+        self.report(Severity::Warning, code, span, msg);
+    }
+
+    fn report_xml_error(&self, e: &XmlError) {
+        let pos = e.position();
+        self.report(
+            Severity::Error,
+            ErrorCode::XmlError,
+            Span::at(pos.row as usize, pos.column as usize),
+            e.msg().to_string()
+        );
+    }
+
+    fn current_span(&self) -> Span {
+        let pos = self.parser.position();
+        Span::at(pos.row as usize, pos.column as usize)
+    }
+}
+
+// ======================================== //
+//                  HELPERS                 //
+// ======================================== //
+
+/// Span of a position inside a text event.
+/// `offset` is relative to `start`, the text event position.
+fn text_span(start: (usize, usize), offset: (usize, usize)) -> Span {
+    match offset {
+        (0, col) => Span::at(start.0, start.1 + col),
+        (row, col) => Span::at(start.0 + row, col),
+    }
 }
 
 #[cfg(test)]
@@ -387,6 +432,9 @@ mod test {
 
     use std::io::BufReader;
     use EmptyErrorReporter;
+    use VecErrorReporter;
+    use diagnostic::{ErrorCode, Severity, Span};
+    use oil_shared::markup::NodeType;
 
     #[test]
     fn reject_invalid_root_tags() {
@@ -477,4 +525,75 @@ mod test {
         assert_eq!(res.views.values().next().unwrap().children.len(), 1);
         assert_eq!(res.templates.len(), 0);
     }
+
+    #[test]
+    fn virtual_attribute_should_be_read_on_repeat() {
+        let reader = BufReader::new(
+            "<view>\
+                <repeat iter=\"{{auctions}}\" template-name=\"auction\" virtual=\"true\"/>\
+                <repeat iter=\"{{friends}}\" template-name=\"friend\"/>\
+             </view>
+            ".as_bytes());
+        let mut parser = super::Parser::new(EmptyErrorReporter, reader);
+
+        let res = parser.parse();
+        let view = res.views.values().next().unwrap();
+        let virtuals: Vec<_> = view.children.iter()
+            .map(|c| match c.node_type {
+                NodeType::Repeat(ref data) => data.is_virtual,
+                _ => panic!("Expected a repeat"),
+            })
+            .collect();
+
+        assert_eq!(virtuals, vec![true, false]);
+    }
+
+    #[test]
+    fn report_position_of_unknown_tags() {
+        let reader = BufReader::new(
+            "<view>\n  <toto />\n</view>".as_bytes());
+        let reporter = VecErrorReporter::new();
+        let mut parser = super::Parser::with_file_name(
+            reporter.clone(), reader, "test.markup".to_string());
+
+        parser.parse();
+
+        let diagnostics = reporter.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].code, ErrorCode::UnknownTag);
+        assert_eq!(diagnostics[0].span, Some(Span::at(1, 2)));
+        assert_eq!(diagnostics[0].to_string(), "Warning test.markup:2:3 : Unknown tag `toto`");
+    }
+
+    #[test]
+    fn report_position_of_ill_formed_data_bindings() {
+        let reader = BufReader::new(
+            "<view>\n  <button>abc {{ foo }</button>\n</view>".as_bytes());
+        let reporter = VecErrorReporter::new();
+        let mut parser = super::Parser::new(reporter.clone(), reader);
+
+        parser.parse();
+
+        let diagnostics = reporter.diagnostics();
+        assert_eq!(diagnostics[0].code, ErrorCode::InvalidDataBinding);
+        assert_eq!(diagnostics[0].to_string(), "Warning 2:15 : Missing '}' for data binding");
+    }
+
+    #[test]
+    fn report_missing_attributes() {
+        let reader = BufReader::new(
+            "<view>\
+                <repeat iter=\"{test}\"/>\
+                <repeat template-name=\"test\"/>\
+             </view>
+            ".as_bytes());
+        let reporter = VecErrorReporter::new();
+        let mut parser = super::Parser::new(reporter.clone(), reader);
+
+        parser.parse();
+
+        assert_eq!(reporter.count_code(ErrorCode::MissingAttribute), 2);
+        assert_eq!(reporter.count(Severity::Error), 0);
+    }
 }
@@ -5,6 +5,7 @@ use super::ErrorType;
 use super::ErrorStatus;
 use super::lookup_name;
 use super::HasNodeChildren;
+use diagnostic::ErrorCode;
 
 use oil_shared::markup::{
     Node,
@@ -69,6 +70,7 @@ pub fn parse_template(attributes: &Vec<OwnedAttribute>) -> ResOrError {
             Err((
                 ErrorType::Warning,
                 ErrorStatus::NotReported(
+                    ErrorCode::MissingAttribute,
                     "`path` attribute in `template` is missing")
             ))
         }
@@ -83,15 +85,22 @@ pub fn parse_repeat(attributes: &Vec<OwnedAttribute>) -> ResOrError {
            lookup_name("iter", attributes))
     {
         (Some(name), Some(iter)) => {
+            // The iter attribute can be written as a data binding:
+            // `iter="{{player.friends}}"`
+            let iter = iter.trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace());
+            let is_virtual = lookup_name("virtual", attributes)
+                .map_or(false, |v| v.trim() == "true");
             Ok(NodeType::Repeat(RepeatData {
                 template_name: name,
-                iter: iter
+                iter: iter.to_string(),
+                is_virtual: is_virtual,
             }))
         }
         (None, _) => {
             Err((
                 ErrorType::Warning,
                 ErrorStatus::NotReported(
+                    ErrorCode::MissingAttribute,
                     "`template-name` attribute in `repeat` is missing")
             ))
         }
@@ -99,6 +108,7 @@ pub fn parse_repeat(attributes: &Vec<OwnedAttribute>) -> ResOrError {
             Err((
                 ErrorType::Warning,
                 ErrorStatus::NotReported(
+                    ErrorCode::MissingAttribute,
                     "`iter` attribute in `repeat` is missing")
             ))
         }
@@ -2,6 +2,7 @@ use std::f32;
 use std::io::Read;
 use std::io::Chars;
 use super::Error;
+use diagnostic::ErrorCode;
 
 
 pub struct BufferConsumer<B> {
@@ -39,7 +40,8 @@ impl<B> BufferConsumer<B>
         let num: String = try!(self.consume_while(is_numeric));
         f32::from_str_radix(&num, 10)
             .map_err(|err| {
-                Error::new(self.row, self.col, format!("Incorrect float value: {}", err))
+                Error::with_code(self.row, self.col, ErrorCode::InvalidNumber,
+                    format!("Incorrect float value: {}", err))
             })
     }
 
@@ -55,14 +57,14 @@ impl<B> BufferConsumer<B>
                 Ok(())
             }
             Some(c) => {
-                Err(self.error_str(format!(
+                Err(self.error_code(ErrorCode::UnexpectedChar, format!(
                     "Expected character `{}` found: `{}`",
                     expect,
                     c
                 )))
             }
             _ => {
-                Err(self.error_str(format!(
+                Err(self.error_code(ErrorCode::UnexpectedEof, format!(
                     "Unexpected end of stream, expected `{}`",
                     expect
                 )))
@@ -92,35 +94,41 @@ impl<B> BufferConsumer<B>
     }
 
     pub fn consume_any_char(&mut self) -> Option<char> {
-        if self.tmp_char.is_none() {
-            match self.buffer.next().and_then(|a| a.ok()) {
-                Some(c) => {
-                    if c == '\n' {
-                        self.row += 1;
-                        self.col = 0;
-                    } else {
-                        self.col += 1;
-                    }
-                    Some(c)
-                }
-                None => None
+        let next = match self.tmp_char.take() {
+            Some(c) => Some(c),
+            None => self.buffer.next().and_then(|a| a.ok()),
+        };
+
+        // Position is updated only once the character is really consumed,
+        // so that it always refers to the next character to be read.
+        match next {
+            Some('\n') => {
+                self.row += 1;
+                self.col = 0;
             }
-        } else {
-            let c = self.tmp_char.unwrap();
-            self.tmp_char = None;
-            Some(c)
+            Some(_) => self.col += 1,
+            None => ()
         }
+
+        next
     }
 
     pub fn look_next_char(&mut self) -> Option<char> {
 
         if self.tmp_char.is_none() {
-            self.tmp_char = self.consume_any_char();
+            self.tmp_char = self.buffer.next().and_then(|a| a.ok());
         }
 
         self.tmp_char
     }
 
+    /// Returns the position (row, col) of the next character
+    /// to be consumed. Both start at 0.
+    #[inline]
+    pub fn position(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
     pub fn error(&self, msg: &str) -> Error {
         Error::new(self.row, self.col, msg.to_string())
     }
@@ -129,8 +137,12 @@ impl<B> BufferConsumer<B>
         Error::new(self.row, self.col, msg)
     }
 
+    pub fn error_code(&self, code: ErrorCode, msg: String) -> Error {
+        Error::with_code(self.row, self.col, code, msg)
+    }
+
     pub fn error_eof(&self) -> Error {
-        Error::new(self.row, self.col, "Unexpected end of stream".to_string())
+        self.error_code(ErrorCode::UnexpectedEof, "Unexpected end of stream".to_string())
     }
 }
 
@@ -180,4 +192,16 @@ mod test {
         assert_eq!(consumer.consume_any_char(), Some('c'));
         assert_eq!(consumer.consume_any_char(), Some('d'));
     }
+
+    #[test]
+    fn position_should_point_to_the_next_char() {
+        let text = "a\nbc";
+        let mut consumer = BufferConsumer::new(text.as_bytes());
+        consumer.consume_any_char();
+        assert_eq!(consumer.look_next_char(), Some('\n'));
+        assert_eq!(consumer.position(), (0, 1));
+        consumer.consume_any_char();
+        consumer.consume_any_char();
+        assert_eq!(consumer.position(), (1, 1));
+    }
 }
@@ -1,9 +1,12 @@
 use std::fmt;
 
+use diagnostic::{Diagnostic, Severity, ErrorCode, Span};
+
 #[derive(Debug)]
 pub struct Error {
     row: usize,
     col: usize,
+    code: ErrorCode,
     msg: String
 }
 
@@ -16,14 +19,26 @@ impl fmt::Display for Error {
 
 
 impl Error {
-    /// Creates a new error using position information from the provided
-    /// `HasPosition` object and a message.
+    /// Creates a new syntax error at the given position.
     #[inline]
     pub fn new(row: usize, col: usize, msg: String) -> Error {
+        Error::with_code(row, col, ErrorCode::InvalidSyntax, msg)
+    }
+
+    #[inline]
+    pub fn with_code(row: usize, col: usize, code: ErrorCode, msg: String) -> Error {
         Error {
             row: row,
             col: col,
+            code: code,
             msg: msg
         }
     }
+
+    /// Convert this error into a `Diagnostic` with severity `Error`.
+    pub fn into_diagnostic(self, file: Option<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, self.code, self.msg)
+            .with_span(Span::at(self.row, self.col))
+            .with_file(file)
+    }
 }
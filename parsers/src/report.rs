@@ -1,7 +1,11 @@
+use std::rc::Rc;
+use std::cell::{Ref, RefCell};
+
+use diagnostic::{Diagnostic, Severity, ErrorCode};
 
 pub trait ErrorReporter: Clone {
 
-    fn log(&self, msg: String);
+    fn report(&self, diagnostic: Diagnostic);
 }
 
 #[derive(Clone)]
@@ -9,12 +13,20 @@ pub struct StdOutErrorReporter;
 #[derive(Clone)]
 pub struct EmptyErrorReporter;
 
+/// Keep all the diagnostics reported.
+///
+/// Clones share the same list, so you can give a clone to
+/// a parser and look at the diagnostics afterward.
+#[derive(Clone)]
+pub struct VecErrorReporter {
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+}
 
 impl ErrorReporter for StdOutErrorReporter {
 
     #[inline]
-    fn log(&self, msg: String) {
-        println!("{}", msg);
+    fn report(&self, diagnostic: Diagnostic) {
+        println!("{}", diagnostic);
     }
 }
 
@@ -22,7 +34,40 @@ impl ErrorReporter for EmptyErrorReporter {
 
     #[inline]
     #[allow(unused_variables)]
-    fn log(&self, msg: String) {
+    fn report(&self, diagnostic: Diagnostic) {
         // Does nothing
     }
 }
+
+impl VecErrorReporter {
+
+    pub fn new() -> VecErrorReporter {
+        VecErrorReporter {
+            diagnostics: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub fn diagnostics(&self) -> Ref<Vec<Diagnostic>> {
+        self.diagnostics.borrow()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics().iter().filter(|d| d.severity == severity).count()
+    }
+
+    pub fn count_code(&self, code: ErrorCode) -> usize {
+        self.diagnostics().iter().filter(|d| d.code == code).count()
+    }
+
+    pub fn clear(&self) {
+        self.diagnostics.borrow_mut().clear();
+    }
+}
+
+impl ErrorReporter for VecErrorReporter {
+
+    #[inline]
+    fn report(&self, diagnostic: Diagnostic) {
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+}
@@ -1,5 +1,7 @@
 
-use std::io::BufRead;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::fs::File;
 
 use report::ErrorReporter;
 use oil_shared::deps::StyleDefinitions;
@@ -19,6 +21,32 @@ pub fn parse<'a, 'b, R, E, B>(
           B: BufRead,
           R: BasicResourceManager
 {
-    let mut parser = parser::Parser::new(reporter, reader, defs, resource_manager);
+    let mut parser = parser::Parser::new(reporter, reader, defs, resource_manager, None);
+    parser.parse()
+}
+
+/// Parse a given style file. Same as `parse()`, except
+/// that the diagnostics refer to the file.
+///
+/// ## Panics
+///
+/// This function panics if the file can't be found.
+pub fn parse_file<'a, 'b, R, E, P>(
+    reporter: E,
+    path: P,
+    defs: &'a StyleDefinitions,
+    resource_manager: &'b mut R) -> Stylesheet
+    where E: ErrorReporter,
+          P: AsRef<Path>,
+          R: BasicResourceManager
+{
+    let reader = BufReader::new(File::open(path.as_ref()).unwrap());
+    let mut parser = parser::Parser::new(
+        reporter,
+        reader,
+        defs,
+        resource_manager,
+        Some(path.as_ref().display().to_string())
+    );
     parser.parse()
 }
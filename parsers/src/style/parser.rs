@@ -3,11 +3,14 @@ use report::ErrorReporter;
 use oil_shared::deps::StyleDefinitions;
 use oil_shared::deps::Constructor;
 use oil_shared::asset;
+use oil_shared::color::Color;
 use std::io::BufRead;
 use std::ops::Deref;
 use parsing::Error;
 use parsing::BufferConsumer;
+use diagnostic::ErrorCode;
 use oil_shared::resource::BasicResourceManager;
+use oil_shared::properties::{PropertyName, STYLE_PROPERTIES};
 use phf;
 
 use oil_shared::style::{
@@ -27,6 +30,7 @@ pub struct Parser<'a, 'b, R: 'b, E, B> {
     bc: BufferConsumer<B>,
     deps: &'a StyleDefinitions,
     resource_manager: &'b mut R,
+    file_name: Option<String>,
 }
 
 impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
@@ -39,13 +43,15 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
         reporter: E,
         reader: B,
         deps: &'a StyleDefinitions,
-        resource_manager: &'b mut R) -> Parser<'a,'b, R, E, B>
+        resource_manager: &'b mut R,
+        file_name: Option<String>) -> Parser<'a,'b, R, E, B>
     {
         Parser {
             bc: BufferConsumer::new(reader),
             err: reporter,
             deps: deps,
             resource_manager: resource_manager,
+            file_name: file_name,
         }
     }
 
@@ -74,8 +80,9 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
                     stylesheet.rules.push(rule);
                 }
                 Err(err) => {
-                    self.err.log(format!("Error {}", err));
-                    break 'rule;
+                    // Only this rule is lost, resume after its `}`.
+                    self.err.report(err.into_diagnostic(self.file_name.clone()));
+                    self.skip_rule();
                 }
             }
         }
@@ -89,10 +96,10 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
         let mut declarations = Vec::new();
 
         try!(self.bc.consume_whitespace());
-        match self.bc.consume_any_char() {
-            Some('{') => (),
+        match self.bc.look_next_char() {
+            Some('{') => self.bc.consume_any_char(),
             _ => return Err(self.bc.error("Rule must start with a `{`"))
-        }
+        };
 
 
         // Loop for declaration.
@@ -102,8 +109,14 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
             match self.bc.look_next_char() {
                 Some('}') => break 'decl,
                 Some(_) => {
-                    let decl =  try!(self.parse_declaration());
-                    declarations.push(decl);
+                    match self.parse_declaration() {
+                        Ok(decl) => declarations.push(decl),
+                        Err(err) => {
+                            // Only this declaration is lost.
+                            self.err.report(err.into_diagnostic(self.file_name.clone()));
+                            self.skip_declaration();
+                        }
+                    }
                 }
                 None => return Err(self.bc.error("Selector must end with a `}`"))
             }
@@ -126,14 +139,16 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
             _ => return Err(self.bc.error("Selector must start with a `.`"))
         }
         let name = try!(self.bc.consume_identifier());
-        let state = match self.bc.consume_any_char() {
+        let state = match self.bc.look_next_char() {
             Some(':') => {
+                self.bc.consume_any_char();
                 let state = try!(self.bc.consume_word());
                 if let Some(&s) = KEYWORDS_SELECTOR_STATE.get(state.deref()) {
                     Some(s)
                 } else {
                     // TODO: Use a warning instead.
-                    return Err(self.bc.error_str(
+                    return Err(self.bc.error_code(
+                        ErrorCode::UnknownSelectorState,
                         format!("Unknown selector state: `{}`", state)
                     ))
                 }
@@ -155,19 +170,50 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
         let name = try!(self.bc.consume_identifier());
 
         try!(self.bc.consume_whitespace());
-        match self.bc.consume_any_char() {
-            Some(':') => (),
+        match self.bc.look_next_char() {
+            Some(':') => self.bc.consume_any_char(),
             _ => return Err(self.bc.error("Invalid identifier expected `:`"))
+        };
+
+        let mut values = vec![try!(self.parse_value())];
+
+        loop {
+            try!(self.bc.consume_whitespace());
+            match self.bc.look_next_char() {
+                Some(';') | Some('}') | None => break,
+                _ => values.push(try!(self.parse_value())),
+            }
         }
 
-        let value = try!(self.parse_value());
+        if values.len() > 1 && !is_track_list(&name) {
+            return Err(self.bc.error_code(
+                ErrorCode::UnexpectedList,
+                format!("`{}` takes a single value", name)
+            ));
+        }
 
-        try!(self.bc.consume_whitespace());
-        match self.bc.consume_any_char() {
-            Some(';') => (),
-            _ => return Err(self.bc.error("Declaration should end with `;`"))
+        for value in values.iter() {
+            if let Value::Length(_, unit) = *value {
+                if !is_unit_allowed(&name, unit) {
+                    return Err(self.bc.error_code(
+                        ErrorCode::InvalidUnit,
+                        format!("Invalid unit for `{}`", name)
+                    ));
+                }
+            }
         }
 
+        let value = if values.len() == 1 {
+            values.pop().unwrap()
+        } else {
+            Value::List(values)
+        };
+
+        match self.bc.look_next_char() {
+            Some(';') => self.bc.consume_any_char(),
+            _ => return Err(self.bc.error("Declaration should end with `;`"))
+        };
+
         Ok(Declaration {
             name: name,
             value: value
@@ -187,12 +233,14 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
                             if let Some(val) = convert_to_style_value(v, self.resource_manager) {
                                 Ok(val)
                             } else {
-                                Err(self.bc.error_str(
+                                Err(self.bc.error_code(
+                                    ErrorCode::ResourceLoading,
                                     format!("Resource `{}` failed loading.", path)
                                 ))
                             }
                         }
-                        None => Err(self.bc.error_str(
+                        None => Err(self.bc.error_code(
+                            ErrorCode::UnknownDefinition,
                             format!("Couldn't find `{}` in style definitions", path)
                         ))
                     }
@@ -202,24 +250,108 @@ impl<'a, 'b, R, E, B> Parser<'a, 'b, R, E, B>
                     let unit = try!(self.consume_unit());
                     Ok(Value::Length(val, unit))
                 }
+                '#' => {
+                    self.bc.consume_any_char();
+                    let hex = try!(self.bc.consume_identifier());
+                    match Color::from_hex(&hex) {
+                        Some(color) => Ok(Value::Color(color)),
+                        None => Err(self.bc.error_code(
+                            ErrorCode::InvalidColor,
+                            format!("Invalid color: `#{}`", hex)
+                        ))
+                    }
+                }
                 _ => {
                     let keyword = try!(self.bc.consume_identifier());
-                    if let Some(&k) = KEYWORDS.get(keyword.deref()) {
+                    if keyword == "rgb" || keyword == "rgba" {
+                        self.parse_color_function(keyword == "rgba")
+                    } else if let Some(&k) = KEYWORDS.get(keyword.deref()) {
                         Ok(Value::Keyword(k))
                     } else {
-                        Err(self.bc.error_str(
+                        Err(self.bc.error_code(
+                            ErrorCode::UnknownKeyword,
                             format!("Unknown keyword: `{}`", keyword)
                         ))
                     }
                 }
             },
-            None => Err(self.bc.error("Unexpected end of input. Expected Value."))
+            None => Err(self.bc.error_code(
+                ErrorCode::UnexpectedEof,
+                "Unexpected end of input. Expected Value.".to_string()))
+        }
+    }
+
+    /// Parse the arguments of `rgb(r, g, b)` or `rgba(r, g, b, a)`.
+    fn parse_color_function(&mut self, has_alpha: bool) -> Result<Value, Error> {
+
+        try!(self.bc.consume_whitespace());
+        try!(self.bc.expect_char('('));
+
+        let mut args = Vec::new();
+        loop {
+            try!(self.bc.consume_whitespace());
+            args.push(try!(self.bc.consume_number()));
+            try!(self.bc.consume_whitespace());
+            match self.bc.consume_any_char() {
+                Some(',') => (),
+                Some(')') => break,
+                _ => return Err(self.bc.error("Expected `,` or `)`"))
+            }
+        }
+
+        match (has_alpha, args.len()) {
+            (false, 3) => Ok(Value::Color(Color::from_rgba(args[0], args[1], args[2], 1.0))),
+            (true, 4) => Ok(Value::Color(Color::from_rgba(args[0], args[1], args[2], args[3]))),
+            _ => Err(self.bc.error_code(
+                ErrorCode::InvalidColor,
+                format!("Wrong number of arguments for `{}`", if has_alpha { "rgba" } else { "rgb" })
+            ))
         }
     }
 
     fn consume_unit(&mut self) -> Result<Unit, Error> {
-        try!(self.bc.consume_identifier());
-        Ok(Unit::Px)
+        if let Some('%') = self.bc.look_next_char() {
+            self.bc.consume_any_char();
+            return Ok(Unit::Percent);
+        }
+
+        let unit = try!(self.bc.consume_identifier());
+        match unit.deref() {
+            "" | "px" => Ok(Unit::Px),
+            "vw" => Ok(Unit::Vw),
+            "vh" => Ok(Unit::Vh),
+            "rem" => Ok(Unit::Rem),
+            "fr" => Ok(Unit::Fr),
+            _ => Err(self.bc.error_code(
+                ErrorCode::UnknownUnit,
+                format!("Unknown unit: `{}`", unit)
+            ))
+        }
+    }
+
+    /// Skip everything until the end of the current declaration.
+    /// A `;` is consumed, a `}` is left for the rule.
+    fn skip_declaration(&mut self) {
+        loop {
+            match self.bc.look_next_char() {
+                Some(';') => {
+                    self.bc.consume_any_char();
+                    return;
+                }
+                Some('}') | None => return,
+                Some(_) => { self.bc.consume_any_char(); }
+            }
+        }
+    }
+
+    /// Skip everything until the end of the current rule (`}` included).
+    fn skip_rule(&mut self) {
+        loop {
+            match self.bc.consume_any_char() {
+                Some('}') | None => return,
+                Some(_) => ()
+            }
+        }
     }
 }
 
@@ -228,7 +360,27 @@ static KEYWORDS: phf::Map<&'static str, KwValue> = phf_map! {
     "expand" => KwValue::Expand,
     "absolute" => KwValue::Absolute,
     "fit" => KwValue::Fit,
-    "repeat" => KwValue::Repeat
+    "repeat" => KwValue::Repeat,
+    "slice" => KwValue::Slice,
+    "left" => KwValue::Left,
+    "center" => KwValue::Center,
+    "right" => KwValue::Right,
+    "normal" => KwValue::Normal,
+    "nowrap" => KwValue::NoWrap,
+    "pre" => KwValue::Pre,
+    "pre-line" => KwValue::PreLine,
+    "row" => KwValue::Row,
+    "column" => KwValue::Column,
+    "start" => KwValue::Start,
+    "end" => KwValue::End,
+    "stretch" => KwValue::Stretch,
+    "space-between" => KwValue::SpaceBetween,
+    "space-around" => KwValue::SpaceAround,
+    "space-evenly" => KwValue::SpaceEvenly,
+    "grid" => KwValue::Grid,
+    "visible" => KwValue::Visible,
+    "hidden" => KwValue::Hidden,
+    "scroll" => KwValue::Scroll,
 };
 
 static KEYWORDS_SELECTOR_STATE: phf::Map<&'static str, SelectorState> = phf_map! {
@@ -237,6 +389,33 @@ static KEYWORDS_SELECTOR_STATE: phf::Map<&'static str, SelectorState> = phf_map!
     "creation" => SelectorState::Creation,
 };
 
+/// Only the track definitions of a grid take a list of values.
+fn is_track_list(name: &str) -> bool {
+    match STYLE_PROPERTIES.get(name) {
+        Some(&PropertyName::GRID_COLUMNS)
+        | Some(&PropertyName::GRID_ROWS) => true,
+        _ => false,
+    }
+}
+
+/// Returns false if `unit` makes no sense for the property `name`.
+fn is_unit_allowed(name: &str, unit: Unit) -> bool {
+    // A fraction of the free space only makes sense for a track.
+    if unit == Unit::Fr {
+        return is_track_list(name);
+    }
+    match STYLE_PROPERTIES.get(name) {
+        // Plain numbers, a number without unit is read as `px`.
+        Some(&PropertyName::OPACITY)
+        | Some(&PropertyName::Z_INDEX)
+        | Some(&PropertyName::FLEX_GROW)
+        | Some(&PropertyName::FLEX_SHRINK) => unit == Unit::Px,
+        // Pixels of the image, or a percentage of its size.
+        Some(&PropertyName::BACKGROUND_SLICE) => unit == Unit::Px || unit == Unit::Percent,
+        _ => true,
+    }
+}
+
 fn convert_to_style_value<R>(ctor: &Constructor, resource_manager: &mut R)
     -> Option<Value>
     where R: BasicResourceManager
@@ -247,8 +426,173 @@ fn convert_to_style_value<R>(ctor: &Constructor, resource_manager: &mut R)
             Some(&k) => Some(Value::Keyword(k)),
             _ => None
         },
-        Constructor::Font(..) => Some(Value::Font(asset::FontData::new(ctor))),
+        Constructor::Color(color) => Some(Value::Color(color)),
+        Constructor::Font(..) => asset::FontData::new(ctor, resource_manager).map(Value::Font),
         Constructor::Image(..) => Some(Value::Image(asset::ImageData::new(ctor, resource_manager))),
         Constructor::None => None,
     }
 }
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::io::BufReader;
+    use oil_shared::deps::StyleDefinitions;
+    use oil_shared::resource::create_null_manager;
+    use oil_shared::color::Color;
+    use oil_shared::style::{Stylesheet, Value, Unit};
+    use VecErrorReporter;
+    use Severity;
+    use ErrorCode;
+
+    fn parse(reporter: VecErrorReporter, text: &str) -> Stylesheet {
+        let defs = StyleDefinitions::new();
+        let mut rm = create_null_manager();
+        let reader = BufReader::new(text.as_bytes());
+        let mut parser = super::Parser::new(reporter, reader, &defs, &mut rm, None);
+        parser.parse()
+    }
+
+    #[test]
+    fn invalid_declaration_should_only_drop_itself() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { width: 10px; height: foo; margin: 2px; }\n\
+             .b { width: 5px; }");
+
+        assert_eq!(reporter.count(Severity::Error), 1);
+        assert_eq!(style.rules.len(), 2);
+        assert_eq!(style.rules[0].declarations.len(), 2);
+        assert_eq!(style.rules[1].declarations.len(), 1);
+    }
+
+    #[test]
+    fn missing_semicolon_should_not_eat_next_rule() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { width: 10px }\n\
+             .b { width: 5px; }");
+
+        assert_eq!(reporter.count(Severity::Error), 1);
+        assert_eq!(style.rules.len(), 2);
+        assert_eq!(style.rules[0].declarations.len(), 0);
+        assert_eq!(style.rules[1].declarations.len(), 1);
+    }
+
+    #[test]
+    fn units_should_be_parsed() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { width: 50%; height: 10vh; margin: 2rem; padding: 3px; opacity: 1; }\n\
+             .b { width: 2em; }");
+
+        assert_eq!(reporter.count_code(ErrorCode::UnknownUnit), 1);
+        assert_eq!(style.rules[1].declarations.len(), 0);
+        let units: Vec<_> = style.rules[0].declarations.iter()
+            .map(|d| match d.value {
+                Value::Length(_, unit) => unit,
+                _ => panic!("Expected a length"),
+            })
+            .collect();
+        assert_eq!(units, vec![Unit::Percent, Unit::Vh, Unit::Rem, Unit::Px, Unit::Px]);
+    }
+
+    #[test]
+    fn units_of_plain_numbers_should_be_reported() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { opacity: 50%; z-index: 2rem; flex-grow: 1; background-slice: 10%; }\n\
+             .b { line-height: 1.5rem; background-slice: 2vw; }");
+
+        assert_eq!(reporter.count_code(ErrorCode::InvalidUnit), 3);
+        assert_eq!(style.rules[0].declarations.len(), 2);
+        assert_eq!(style.rules[1].declarations.len(), 1);
+    }
+
+    #[test]
+    fn lists_should_be_parsed() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { grid-columns: 100px 1fr auto; grid-rows: 2fr; }");
+
+        assert_eq!(reporter.count(Severity::Error), 0);
+        match style.rules[0].declarations[0].value {
+            Value::List(ref values) => {
+                assert_eq!(values.len(), 3);
+                match values[1] {
+                    Value::Length(v, Unit::Fr) => assert_eq!(v, 1.0),
+                    _ => panic!("Expected a fraction"),
+                }
+            }
+            _ => panic!("Expected a list"),
+        }
+        match style.rules[0].declarations[1].value {
+            Value::Length(v, Unit::Fr) => assert_eq!(v, 2.0),
+            _ => panic!("Expected a single fraction"),
+        }
+    }
+
+    #[test]
+    fn lists_and_fractions_outside_tracks_should_be_reported() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { margin: 10px 20px; width: 1fr; height: 10px; }\n\
+             .b { grid-columns: 1fr 20px; grid-rows: 1fr; }");
+
+        assert_eq!(reporter.count_code(ErrorCode::UnexpectedList), 1);
+        assert_eq!(reporter.count_code(ErrorCode::InvalidUnit), 1);
+        assert_eq!(style.rules[0].declarations.len(), 1);
+        assert_eq!(style.rules[1].declarations.len(), 2);
+    }
+
+    #[test]
+    fn colors_should_be_parsed() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a { color: #ff000080; background-color: rgb(0, 128, 255); }\n\
+             .b { color: rgba(0, 0, 0, 0.5); background-color: #12; }");
+
+        assert_eq!(reporter.count_code(ErrorCode::InvalidColor), 1);
+        let colors: Vec<_> = style.rules.iter()
+            .flat_map(|r| r.declarations.iter())
+            .map(|d| match d.value {
+                Value::Color(c) => c,
+                _ => panic!("Expected a color"),
+            })
+            .collect();
+        assert_eq!(colors, vec![
+            Color::new(255, 0, 0, 128),
+            Color::new(0, 128, 255, 255),
+            Color::new(0, 0, 0, 128),
+        ]);
+    }
+
+    #[test]
+    fn invalid_selector_should_only_drop_its_rule() {
+        let reporter = VecErrorReporter::new();
+        let style = parse(reporter.clone(),
+            ".a:unknown { width: 10px; }\n\
+             .b { width: 5px; }");
+
+        assert_eq!(reporter.count(Severity::Error), 1);
+        assert_eq!(style.rules.len(), 1);
+        assert_eq!(style.rules[0].selector.name, "b");
+    }
+
+    #[test]
+    fn diagnostics_should_name_the_file() {
+        let reporter = VecErrorReporter::new();
+        let defs = StyleDefinitions::new();
+        let mut rm = create_null_manager();
+        let reader = BufReader::new(".a { width: foo; }".as_bytes());
+        let mut parser = super::Parser::new(
+            reporter.clone(), reader, &defs, &mut rm, Some("menu.style".to_string()));
+        parser.parse();
+
+        assert_eq!(reporter.diagnostics()[0].file, Some("menu.style".to_string()));
+    }
+}
@@ -4,8 +4,14 @@ use std::path::Path;
 use deps::Constructor;
 use resource::{BasicResourceManager, ResourceId};
 
+/// A font loaded by a `BasicResourceManager`.
 #[derive(Debug, Clone)]
-pub struct FontData;
+pub struct FontData {
+    pub font: ResourceId,
+    /// Glyph size in pixels.
+    pub width: f32,
+    pub height: f32,
+}
 
 // TODO handle shared images somehow
 // even in a disgusting way, but something !
@@ -17,6 +23,32 @@ pub struct ImageData {
     pub offset_y: f32,
     pub width: f32,
     pub height: f32,
+    /// Insets used by the `slice` background rule.
+    pub slice: Option<SliceInsets>,
+}
+
+/// Distances in pixels from the sides of an image, cutting
+/// it in nine parts: the corners are never scaled, the edges
+/// and the center are stretched.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SliceInsets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl SliceInsets {
+
+    /// Same inset on the four sides.
+    pub fn uniform(inset: f32) -> SliceInsets {
+        SliceInsets {
+            left: inset,
+            right: inset,
+            top: inset,
+            bottom: inset,
+        }
+    }
 }
 
 /// Necessary because DynamicImage does not implement the trait Debug.
@@ -28,7 +60,8 @@ impl Debug for ImageData
         try!(write!(f, "offset_x {:?}, ", self.offset_x));
         try!(write!(f, "offset_y {:?}, ", self.offset_y));
         try!(write!(f, "width {:?}, ", self.width));
-        try!(write!(f, "height {:?} ", self.height));
+        try!(write!(f, "height {:?}, ", self.height));
+        try!(write!(f, "slice {:?} ", self.slice));
 
         write!(f, "}}")
     }
@@ -41,7 +74,7 @@ impl ImageData {
         -> ImageData
         where R: BasicResourceManager
     {
-        if let Constructor::Image(ref path, width, height, offset_x, offset_y)
+        if let Constructor::Image(ref path, width, height, offset_x, offset_y, slice)
                 = *image_ctor
         {
             let image = resource_manager.get_texture_id(&Path::new(path));
@@ -57,6 +90,7 @@ impl ImageData {
                 offset_y: y,
                 width: w,
                 height: h,
+                slice: slice,
             }
         } else {
             panic!("Wrong constructor passed. Expected Constructor::Image.");
@@ -66,10 +100,22 @@ impl ImageData {
 
 impl FontData {
 
-    pub fn new(font_ctor: &Constructor) -> FontData {
+    /// Load the font described by `font_ctor`.
+    /// Returns `None` if the resource manager failed loading it.
+    pub fn new<R>(
+        font_ctor: &Constructor,
+        resource_manager: &mut R)
+        -> Option<FontData>
+        where R: BasicResourceManager
+    {
         if let Constructor::Font(ref path, width, height) = *font_ctor {
-            // TODO: see freetype-rs or something similar
-            FontData
+            resource_manager.get_font_id(path, width, height).map(|font| {
+                FontData {
+                    font: font,
+                    width: width,
+                    height: height,
+                }
+            })
         } else {
             panic!("Wrong constructor passed. Expected Constructor::Font.");
         }
@@ -0,0 +1,89 @@
+/// A color with 8 bits per component (rgba order).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+
+    #[inline]
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r: r, g: g, b: b, a: a }
+    }
+
+    /// Parse the hexadecimal notation, without the leading `#`:
+    /// `rgb`, `rgba`, `rrggbb` or `rrggbbaa`.
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let digits: Vec<u8> = match hex.chars().map(|c| c.to_digit(16)).collect::<Option<Vec<_>>>() {
+            Some(digits) => digits.into_iter().map(|d| d as u8).collect(),
+            None => return None,
+        };
+
+        match digits.len() {
+            3 | 4 => {
+                // `f` stands for `ff`
+                let c = |i: usize| digits.get(i).map_or(255, |&d| d * 17);
+                Some(Color::new(c(0), c(1), c(2), c(3)))
+            }
+            6 | 8 => {
+                let c = |i: usize| match (digits.get(2 * i), digits.get(2 * i + 1)) {
+                    (Some(&h), Some(&l)) => h * 16 + l,
+                    _ => 255,
+                };
+                Some(Color::new(c(0), c(1), c(2), c(3)))
+            }
+            _ => None
+        }
+    }
+
+    /// Same as the CSS function `rgba()`: the channels are
+    /// in the range [0, 255] and alpha in the range [0, 1].
+    /// Out of range values are clamped.
+    pub fn from_rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+        let channel = |v: f32| v.max(0.0).min(255.0).round() as u8;
+        Color::new(channel(r), channel(g), channel(b), channel(a * 255.0))
+    }
+
+    /// Components in the range [0, 1], as expected by the renderer.
+    pub fn to_f32(&self) -> [f32; 4] {
+        [
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+            self.a as f32 / 255.0,
+        ]
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use super::Color;
+
+    #[test]
+    fn hex_should_accept_short_and_long_forms() {
+        assert_eq!(Color::from_hex("f80"), Some(Color::new(255, 136, 0, 255)));
+        assert_eq!(Color::from_hex("f808"), Some(Color::new(255, 136, 0, 136)));
+        assert_eq!(Color::from_hex("1a2B3c"), Some(Color::new(26, 43, 60, 255)));
+        assert_eq!(Color::from_hex("1a2b3c80"), Some(Color::new(26, 43, 60, 128)));
+    }
+
+    #[test]
+    fn rgba_should_clamp_its_arguments() {
+        assert_eq!(Color::from_rgba(300.0, -2.0, 127.6, 0.5), Color::new(255, 0, 128, 128));
+    }
+
+    #[test]
+    fn hex_should_reject_invalid_digits_and_lengths() {
+        assert_eq!(Color::from_hex("12345"), None);
+        assert_eq!(Color::from_hex("gg0000"), None);
+        assert_eq!(Color::from_hex(""), None);
+    }
+}
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use asset::SliceInsets;
+use color::Color;
+
 pub struct StyleDefinitions {
     pub defs: HashMap<String, Constructor>,
 }
@@ -25,10 +28,12 @@ pub enum Constructor {
     Number(f32),
     /// String ".+"
     Quote(String),
+    /// Color(r, g, b, a) or Color("#rrggbbaa")
+    Color(Color),
     /// Font(path, width, height)
-    Font(String, f32, f32),
+    Font(PathBuf, f32, f32),
     /// TODO: replace String by the type Path
-    /// Image(path, width, height, offset-x, offset-y)
-    Image(PathBuf, Option<f32>, Option<f32>, Option<f32>, Option<f32>),
+    /// Image(path, width, height, offset-x, offset-y, slice)
+    Image(PathBuf, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<SliceInsets>),
     // Add other construtor here...
 }
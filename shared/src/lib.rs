@@ -4,6 +4,7 @@
 extern crate phf;
 
 pub mod asset;
+pub mod color;
 pub mod resource;
 pub mod style;
 pub mod deps;
@@ -10,6 +10,10 @@ pub enum NodeType {
     ProgressBar(ProgressBarData),
     Template(TemplateData),
     Repeat(RepeatData),
+    /// Data binding inside an instance of a repeat.
+    /// This node is never produced by the parser,
+    /// it is created when a repeat is instantiated.
+    RepeatBinding(RepeatBindingData),
     // Special Root Nodes
     RootView,
     RootTemplate
@@ -111,4 +115,16 @@ pub struct TemplateData {
 pub struct RepeatData {
     pub template_name: String,
     pub iter: String,
+    /// Only the visible items are instantiated (`virtual="true"`).
+    pub is_virtual: bool,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct RepeatBindingData {
+    /// Key of the collection iterated (`iter` attribute of the repeat).
+    pub iter: String,
+    /// Index of the item in the collection.
+    pub index: usize,
+    /// Key of the property, relative to the item.
+    pub key: String,
 }
@@ -13,6 +13,11 @@ pub enum PropertyName {
     BOTTOM,
     HEIGHT,
     WIDTH,
+    // Size constraints
+    MIN_WIDTH,
+    MAX_WIDTH,
+    MIN_HEIGHT,
+    MAX_HEIGHT,
     // Margin properties
     MARGIN,
     MARGIN_LEFT,
@@ -31,18 +36,78 @@ pub enum PropertyName {
     BORDER_RIGHT,
     BORDER_TOP,
     BORDER_BOTTOM,
+    /// Color of the four border edges.
+    /// This property can only have Value::Color.
+    BORDER_COLOR,
+    /// Drawn over the border area, cut in nine parts
+    /// like with the `slice` background rule.
+    /// This property can only have Value::Image.
+    BORDER_IMAGE,
     // Layout mode (absolute / rtl / ltr)
     LAYOUT_MODE,
+    /// Alignment of the children of a `row` or `column` node
+    /// on the cross axis: `start`, `center`, `end` or `stretch`.
+    ALIGN_ITEMS,
+    /// Distribution of the children of a `row` or `column` node
+    /// on the main axis, same as in CSS.
+    JUSTIFY_CONTENT,
+    /// Space between the children of a `row` or `column` node,
+    /// or between the cells of a `grid` node.
+    GAP,
+    /// Tracks of a `grid` node: lengths, fractions (`1fr`) or `auto`.
+    GRID_COLUMNS,
+    GRID_ROWS,
+    /// Share of the free space taken by a child of a `row`
+    /// or `column` node. Defaults to 0.
+    FLEX_GROW,
+    /// Share of the missing space given up by a child of
+    /// a `row` or `column` node. Defaults to 1.
+    FLEX_SHRINK,
+    /// `hidden` clips the children to the content box,
+    /// `scroll` also lets them be scrolled. Defaults to `visible`.
+    OVERFLOW,
+    /// Painting order of an absolute node among its
+    /// absolute siblings, drawn after the other ones.
+    Z_INDEX,
+    /// Size of `1rem`, only read on the root of a view.
+    /// Defaults to 16px.
+    ROOT_UNIT,
 
     /// Background
     /// Possibles rules:
     /// * `"fit"` will scale the image with the node content size.
     /// * `"repeat"` won't scale the image but will repeat it
+    /// * `"slice"` cuts the image in nine parts (see `BACKGROUND_SLICE`),
+    ///   only the edges and the center are scaled
     ///
     /// In any case, the node content bounds will be the final image bounds
     BACKGROUND_IMAGE_RULE,
     /// This property can only have Value::Image.
     BACKGROUND_IMAGE,
+    /// Inset of the `slice` rule, the same for every side.
+    /// Overrides the insets given to the `Image` constructor.
+    BACKGROUND_SLICE,
+    /// Number between 0 and 1, multiplied
+    /// by the opacity of the ancestors.
+    OPACITY,
+    /// Drawn below the background image.
+    /// This property can only have Value::Color.
+    BACKGROUND_COLOR,
+
+    /// Font used by the text of the node and its descendants.
+    /// This property can only have Value::Font.
+    FONT,
+    /// Color of the text, inherited like the font.
+    /// This property can only have Value::Color.
+    COLOR,
+    /// Horizontal alignment of the lines of text:
+    /// `left`, `center` or `right`.
+    TEXT_ALIGN,
+    /// Distance between two baselines.
+    /// Defaults to the line height of the font.
+    LINE_HEIGHT,
+    /// Same as in CSS: `normal`, `nowrap`, `pre` or `pre-line`.
+    WHITE_SPACE,
 }
 
 pub static STYLE_PROPERTIES: phf::Map<&'static str, PropertyName> = phf_map! {
@@ -53,6 +118,11 @@ pub static STYLE_PROPERTIES: phf::Map<&'static str, PropertyName> = phf_map! {
     "bottom" => PropertyName::BOTTOM,
     "height" => PropertyName::HEIGHT,
     "width" => PropertyName::WIDTH,
+    // Size constraints
+    "min-width" => PropertyName::MIN_WIDTH,
+    "max-width" => PropertyName::MAX_WIDTH,
+    "min-height" => PropertyName::MIN_HEIGHT,
+    "max-height" => PropertyName::MAX_HEIGHT,
     // Margin properties
     "margin" => PropertyName::MARGIN,
     "margin-left" => PropertyName::MARGIN_LEFT,
@@ -71,9 +141,31 @@ pub static STYLE_PROPERTIES: phf::Map<&'static str, PropertyName> = phf_map! {
     "border-right" => PropertyName::BORDER_RIGHT,
     "border-top" => PropertyName::BORDER_TOP,
     "border-bottom" => PropertyName::BORDER_BOTTOM,
+    "border-color" => PropertyName::BORDER_COLOR,
+    "border-image" => PropertyName::BORDER_IMAGE,
     // Layout mode (absolute / rtl / ltr)
     "layout" => PropertyName::LAYOUT_MODE,
+    "align-items" => PropertyName::ALIGN_ITEMS,
+    "justify-content" => PropertyName::JUSTIFY_CONTENT,
+    "gap" => PropertyName::GAP,
+    "grid-columns" => PropertyName::GRID_COLUMNS,
+    "grid-rows" => PropertyName::GRID_ROWS,
+    "overflow" => PropertyName::OVERFLOW,
+    "flex-grow" => PropertyName::FLEX_GROW,
+    "flex-shrink" => PropertyName::FLEX_SHRINK,
+    "z-index" => PropertyName::Z_INDEX,
+    "root-unit" => PropertyName::ROOT_UNIT,
     // Background image
     "background-image" => PropertyName::BACKGROUND_IMAGE,
     "background-image-rule" => PropertyName::BACKGROUND_IMAGE_RULE,
+    "background-slice" => PropertyName::BACKGROUND_SLICE,
+    "background-color" => PropertyName::BACKGROUND_COLOR,
+    // Opacity
+    "opacity" => PropertyName::OPACITY,
+    // Text
+    "font" => PropertyName::FONT,
+    "color" => PropertyName::COLOR,
+    "text-align" => PropertyName::TEXT_ALIGN,
+    "line-height" => PropertyName::LINE_HEIGHT,
+    "white-space" => PropertyName::WHITE_SPACE,
 };
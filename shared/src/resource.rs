@@ -1,6 +1,6 @@
 use std::path::Path;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ResourceId(usize);
 
 pub trait BasicResourceManager {
@@ -8,6 +8,15 @@ pub trait BasicResourceManager {
     fn get_texture_id(&mut self, p: &Path) -> ResourceId;
 
     fn get_image_dimensions(&self, id: ResourceId) -> (u32, u32);
+
+    /// Load the font at `p`, rasterized with the given
+    /// glyph size in pixels. Returns `None` if the font
+    /// couldn't be loaded.
+    fn get_font_id(&mut self, p: &Path, width: f32, height: f32) -> Option<ResourceId>;
+
+    /// Returns the size in pixels of `text` written
+    /// on a single line with the font `id`.
+    fn measure_text(&self, id: ResourceId, text: &str) -> (f32, f32);
 }
 
 /// Create a BasicResourceManager that does nothing.
@@ -30,6 +39,17 @@ impl BasicResourceManager for NullBasicResourceManager {
     fn get_image_dimensions(&self, _: ResourceId) -> (u32, u32) {
         (0, 0)
     }
+
+    // No font is ever loaded.
+    fn get_font_id(&mut self, _: &Path, _: f32, _: f32)
+        -> Option<ResourceId>
+    {
+        None
+    }
+
+    fn measure_text(&self, _: ResourceId, _: &str) -> (f32, f32) {
+        (0.0, 0.0)
+    }
 }
 
 
@@ -1,13 +1,13 @@
 
 use asset;
-//use color::alpha::Rgba;
+use color::Color;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Stylesheet {
     pub rules: Vec<Rule>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Rule {
     pub selector: Selector,
     pub declarations: Vec<Declaration>,
@@ -21,13 +21,13 @@ pub enum SelectorState {
     Creation
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Selector {
     pub name: String,
     pub state: SelectorState
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Declaration {
     pub name: String,
     pub value: Value,
@@ -47,10 +47,13 @@ pub struct Declaration {
 #[derive(Debug, Clone)]
 pub enum Value {
     Length(f32, Unit),
-//    ColorValue(Rgba<u8>)
+    Color(Color),
     Font(asset::FontData),
     Image(asset::ImageData),
     Keyword(KwValue),
+    /// Several values separated by whitespaces,
+    /// such as the tracks of a grid.
+    List(Vec<Value>),
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -59,12 +62,49 @@ pub enum KwValue {
     Expand,
     Absolute,
     Fit,
-    Repeat
+    Repeat,
+    Slice,
+    // text-align
+    Left,
+    Center,
+    Right,
+    // white-space
+    Normal,
+    NoWrap,
+    Pre,
+    PreLine,
+    // layout
+    Row,
+    Column,
+    // align-items / justify-content
+    Start,
+    End,
+    Stretch,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+    Grid,
+    // overflow
+    Visible,
+    Hidden,
+    Scroll,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Unit {
+    /// Also used for numbers without unit.
     Px,
+    /// `%` of the parent content box.
+    Percent,
+    /// `%` of the viewport width.
+    Vw,
+    /// `%` of the viewport height.
+    Vh,
+    /// Multiple of the `root-unit` property of the view root.
+    Rem,
+    /// Share of the free space of a grid (`fr`),
+    /// only meaningful for grid tracks.
+    Fr,
 }
 
 impl Stylesheet {
@@ -1,144 +1,381 @@
 use std::collections::HashMap;
+use std::iter::Zip;
+use std::slice::Iter;
 
-// use data_bindings::{StoreValue, DBCLookup};
-use markup::{View, Template, NodeType};
+use data_bindings::{Cast, Store};
+use data_bindings::store::{StoreValueStatic, AsStoreValue};
+use data_bindings::context::ViewContext;
+use markup::{self, View, Template, NodeType, RepeatBindingData, RepeatWindow};
 use util::BufferFromTree;
-use layout::LayoutBuffer;
 use DataBindingsContext;
 
 pub struct DataBindingBuffer {
     bindings: BufferFromTree<DataBindingNode>,
-    // iterators: BufferFromTree<IteratorNode>,
-    // iterator_bindings: BufferFromTree<IteratorBindingNode>,
+    iterators: Vec<IteratorNode>,
 }
 
-struct DataBindingNode {
+pub struct DataBindingNode {
     key: String,
-    // current: StoreValue,
+    /// Iterator and index of the item this binding
+    /// belongs to, if it comes from a repeat.
+    scope: Option<(String, usize)>,
+    /// Index of the repeat instance it belongs to, which
+    /// shows another item when the window of the repeat moves.
+    slot: usize,
+    current: Option<StoreValueStatic>,
 }
 
-// struct IteratorNode {
-//     iterator: String,
-//     number: u32,
-// }
-//
-// struct IteratorBindingNode {
-//     iterator: String,
-//     key: String,
-//     current: Vec<StoreValue>,
-// }
+struct IteratorNode {
+    iter: String,
+    number: usize,
+    is_virtual: bool,
+    /// Items shown by the instances of a virtual repeat,
+    /// the others show all of their items.
+    window: RepeatWindow,
+}
 
 impl DataBindingNode {
-//     fn update<G, V>(&mut self, context: &ContextManager<G, V>, layout: &mut LayoutBuffer, lookup: usize) -> bool {
-//         // TODO
-//     }
 
-    fn new(key: String) -> DataBindingNode {
+    fn new(key: String, scope: Option<(String, usize)>) -> DataBindingNode {
         DataBindingNode {
-            key: key,
-//             current: StoreValue::Integer(0),
+            key: key.trim().to_string(),
+            slot: scope.as_ref().map_or(0, |&(_, index)| index),
+            scope: scope,
+            current: None,
+        }
+    }
+
+    /// Fetch the value from the context and returns true if it
+    /// has changed. `items` are the index of the first item and
+    /// the items of each iterator, see `ViewContext::get_items_in`.
+    fn update<'a>(
+        &mut self,
+        context: &'a ViewContext<'a>,
+        items: &HashMap<String, (usize, Vec<&'a Store>)>) -> bool
+    {
+        let changed = {
+            let new_value = match self.scope {
+                Some((ref iter, index)) => {
+                    let item = items.get(iter).and_then(|&(first, ref items)| {
+                        index.checked_sub(first).and_then(|i| items.get(i))
+                    });
+                    context.get_item_attribute(item.map(|&item| item), &self.key)
+                }
+                None => context.get_attribute(&self.key),
+            };
+            let old_value = self.current.as_ref().map(|v| v.as_store_value());
+            if new_value == old_value {
+                None
+            } else {
+                // A value that can't be cast shows nothing, as a missing
+                // one: it is a change only if something was shown.
+                match new_value.and_then(|v| <StoreValueStatic as Cast>::cast(v)) {
+                    None if self.current.is_none() => None,
+                    new_value => Some(new_value),
+                }
+            }
+        };
+
+        match changed {
+            Some(new_value) => {
+                self.current = new_value;
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Current value of the binding, `None` if the key can't be
+    /// found in the context or its value can't be displayed.
+    pub fn value(&self) -> Option<&StoreValueStatic> {
+        self.current.as_ref()
+    }
+
+    /// Text to display for this binding.
+    pub fn text(&self) -> String {
+        match self.current {
+            Some(ref v) => v.to_string(),
+            None => String::new(),
+        }
+    }
+}
+
+impl IteratorNode {
+
+    fn new(iter: String, is_virtual: bool) -> IteratorNode {
+        IteratorNode {
+            iter: iter,
+            number: 0,
+            is_virtual: is_virtual,
+            window: RepeatWindow::default(),
+        }
+    }
+
+    /// Items shown by the instances of the repeat.
+    fn window(&self) -> RepeatWindow {
+        if self.is_virtual {
+            self.window
+        } else {
+            RepeatWindow { first: 0, slots: self.number }
+        }
+    }
+
+    /// Fetch the number of items from the context and
+    /// returns true if it has changed. An iterator that
+    /// can't be found has no items.
+    fn update(&mut self, context: &ViewContext) -> bool {
+        let new_value = context.get_iterable_len(&self.iter).unwrap_or(0);
+        if new_value != self.number {
+            self.number = new_value;
+            true
+        } else {
+            false
         }
     }
 }
 
-// impl IteratorNode {
-//     fn update(&mut self, context: &ContextManager, layout: &mut LayoutBuffer, lookup: usize) -> bool {
-//         match context.iterator_len(&self.iterator) {
-//             Err(e) => {
-//                 println!("WARNING: Failed to update iterator {}: {}", self.iterator, e);
-//                 false
-//             },
-//             Ok(new_value) => {
-//                 if new_value != self.number {
-//                     self.number = new_value;
-//                     true
-//                 } else {
-//                     false
-//                 }
-//             }
-//         }
-//     }
-//
-//     fn new(it: String) -> IteratorNode {
-//         IteratorNode {
-//             iterator: it,
-//             number: 0,
-//         }
-//     }
-// }
-//
-// impl IteratorBindingNode {
-//     fn update(&mut self, context: &ContextManager, layout: &mut LayoutBuffer, lookup: usize) -> bool {
-//         match context.compare_and_update(&self.iterator, &self.key, &mut self.current) {
-//             Ok(res) => res,
-//             Err(e) => {
-//                 println!("ERROR: Failed to update repeat binding {} {}: {}", self.iterator, self.key, e);
-//                 false
-//             }
-//         }
-//     }
-//
-//     fn new(iterator: String, key: String) -> IteratorBindingNode {
-//         IteratorBindingNode {
-//             iterator: iterator,
-//             key: key,
-//             current: Vec::new(),
-//         }
-//     }
-// }
-//
 impl DataBindingBuffer {
-    pub fn update<C>(&mut self, context: &mut C, layout: &mut LayoutBuffer) -> bool
+
+    /// Create the buffer for the given view. The repeats
+    /// of the view start empty, call `update_iterators`
+    /// to know if they need to be instantiated.
+    pub fn new(view: &View, templates: &HashMap<String, Template>) -> DataBindingBuffer {
+        let iterators: Vec<_> = markup::repeat_iterators(view)
+            .into_iter()
+            .zip(markup::virtual_repeats(view).into_iter())
+            .map(|(iter, is_virtual)| IteratorNode::new(iter, is_virtual))
+            .collect();
+        let lengths = vec![0; iterators.len()];
+        let instance = markup::instantiate_repeats(view, templates, &lengths);
+
+        DataBindingBuffer {
+            bindings: DataBindingBuffer::create_bindings(&instance),
+            iterators: iterators,
+        }
+    }
+
+    /// Resolve all the bindings of the view `view_name`.
+    /// Returns true if at least one of them has changed.
+    pub fn update<C>(&mut self, view_name: &str, context: &C) -> bool
         where C: DataBindingsContext
     {
         let mut has_changed = false;
-//         for (&lookup, node) in self.bindings.enumerate_lookup_indices_mut().unwrap() {
-//             if node.update(context, layout, lookup) {
-//                 has_changed = true;
-//             }
-//         }
-//         for (&lookup, node) in self.iterators.enumerate_lookup_indices_mut().unwrap() {
-//             if node.update(context, layout, lookup) {
-//                 has_changed = true;
-//             }
-//         }
-//         for (&lookup, node) in self.iterator_bindings.enumerate_lookup_indices_mut().unwrap() {
-//             if node.update(context, layout, lookup) {
-//                 has_changed = true;
-//             }
-//         }
+        let view_context = context.get_view_context(view_name);
+        // Only the items shown by the instances are fetched,
+        // once per collection and not once per binding.
+        let items: HashMap<_, _> = self.iterators.iter()
+            .map(|it| {
+                let RepeatWindow { first, slots } = it.window();
+                (it.iter.clone(), (first, view_context.get_items_in(&it.iter, first, slots)))
+            })
+            .collect();
+        for node in self.bindings.iter_mut() {
+            if node.update(&view_context, &items) {
+                has_changed = true;
+            }
+        }
         has_changed
     }
-//
-    pub fn new(view: &View, templates: &HashMap<String, Template>) -> DataBindingBuffer {
-        let bindings = BufferFromTree::new_with_lookup_table(view, 0, |node| {
+
+    /// Fetch the number of items of each repeat of the view `view_name`.
+    /// Returns true if any of them has changed, in which case the view
+    /// needs to be instantiated again with `iterator_lengths`.
+    pub fn update_iterators<C>(&mut self, view_name: &str, context: &C) -> bool
+        where C: DataBindingsContext
+    {
+        let mut has_changed = false;
+        let view_context = context.get_view_context(view_name);
+        for node in self.iterators.iter_mut() {
+            if node.update(&view_context) {
+                has_changed = true;
+            }
+        }
+        has_changed
+    }
+
+    /// Number of items of each repeat, in the order
+    /// expected by `markup::instantiate_repeats`.
+    pub fn iterator_lengths(&self) -> Vec<usize> {
+        self.iterators.iter().map(|it| it.number).collect()
+    }
+
+    /// Make the instances of each repeat show the items of its window,
+    /// in the order of `iterator_lengths`. The bindings whose item
+    /// changed get their new value on the next `update`.
+    pub fn set_repeat_windows(&mut self, windows: &[RepeatWindow]) {
+        for (iterator, window) in self.iterators.iter_mut().zip(windows.iter()) {
+            iterator.window = *window;
+            if window.slots == 0 {
+                continue;
+            }
+            for node in self.bindings.iter_mut() {
+                let slot = node.slot;
+                if let Some((ref iter, ref mut index)) = node.scope {
+                    if *iter == iterator.iter {
+                        *index = window.item_of_slot(slot);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the bindings by the ones of `instance`, which
+    /// must be the view instantiated with `iterator_lengths`.
+    /// The new bindings have no value until the next `update`.
+    pub fn set_instance(&mut self, instance: &View) {
+        self.bindings = DataBindingBuffer::create_bindings(instance);
+    }
+
+    /// Iterate over the bindings along with the global index
+    /// of their node.
+    pub fn enumerate_bindings<'a>(&'a self)
+        -> Zip<Iter<'a, usize>, Iter<'a, DataBindingNode>>
+    {
+        self.bindings.enumerate_lookup_indices().unwrap()
+    }
+
+    fn create_bindings(instance: &View) -> BufferFromTree<DataBindingNode> {
+        BufferFromTree::new_with_lookup_table(instance, 0, |node| {
             match node.node_type {
                 NodeType::Binding(ref binding) => {
-                    Some(DataBindingNode::new(binding.clone()))
+                    Some(DataBindingNode::new(binding.clone(), None))
+                }
+                NodeType::RepeatBinding(RepeatBindingData { ref iter, index, ref key }) => {
+                    Some(DataBindingNode::new(key.clone(), Some((iter.clone(), index))))
                 }
                 _ => None
             }
-        });
-//         let iterators = BufferFromTree::new_with_lookup_table(view, 0, |node| {
-//             match node.node_type {
-//                 // NodeType::Repeat(RepeatData { ref template_name, ref iter }) => {
-//                 //     Some(IteratorNode::new(iterator.clone()))
-//                 // }
-//                 _ => None
-//             }
-//         });
-//         let iterator_bindings = BufferFromTree::new_with_lookup_table(view, 0, |node| {
-//             match node.node_type {
-//                 // NodeType::RepeatBinding(RepeatBindingData{ref iter, ref key}) => {
-//                 //     Some(IteratorBindingNode::new(iter.clone(), key.clone()))
-//                 // }
-//                 _ => None
-//             }
-//         });
-        DataBindingBuffer {
-            bindings: bindings,
-//             iterators: iterators,
-//             iterator_bindings: iterator_bindings,
-        }
+        })
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::io::BufReader;
+    use std::collections::HashMap;
+    use markup::{self, RepeatWindow};
+    use oil_parsers::EmptyErrorReporter;
+    use data_bindings::DefaultContextManager;
+    use super::DataBindingBuffer;
+
+    #[test]
+    fn update_should_detect_changed_values() {
+        let reader = BufReader::new(
+            "<view>\
+                <button>{{ option.width }}</button>\
+             </view>".as_bytes());
+        let lib = markup::parse(EmptyErrorReporter, reader);
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+        let name = markup::MAIN_VIEW_NAME.to_string();
+
+        let mut buffer = DataBindingBuffer::new(view, &lib.templates);
+        let mut context = DefaultContextManager::default();
+        context.register_global_value("option.width".to_string(), 42);
+
+        assert!(buffer.update(&name, &context));
+        assert!(!buffer.update(&name, &context));
+
+        context.register_global_value("option.width".to_string(), 43);
+        assert!(buffer.update(&name, &context));
+
+        let (&i, node) = buffer.enumerate_bindings().next().unwrap();
+        assert_eq!(i, 2);
+        assert_eq!(node.text(), "43");
+    }
+
+    #[test]
+    fn bindings_that_cant_be_shown_should_not_change() {
+        let reader = BufReader::new(
+            "<view>\
+                <button>{{ friends }}</button>\
+             </view>".as_bytes());
+        let lib = markup::parse(EmptyErrorReporter, reader);
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+        let name = markup::MAIN_VIEW_NAME.to_string();
+
+        let mut buffer = DataBindingBuffer::new(view, &lib.templates);
+        let mut context = DefaultContextManager::default();
+        let friends: Vec<HashMap<String, u32>> = vec![HashMap::new()];
+        context.register_global_store("friends".to_string(), friends);
+
+        // A collection has no text, the node is never dirty.
+        assert!(!buffer.update(&name, &context));
+        assert!(!buffer.update(&name, &context));
+        let (_, node) = buffer.enumerate_bindings().next().unwrap();
+        assert_eq!(node.text(), "");
+    }
+
+    #[test]
+    fn repeat_bindings_should_resolve_their_item() {
+        let reader = BufReader::new(
+            "<view>\
+                <repeat iter=\"{{ friends }}\" template-name=\"friend\"/>\
+             </view>\
+             <template name=\"friend\">\
+                <button>{{ score }}</button>\
+             </template>".as_bytes());
+        let lib = markup::parse(EmptyErrorReporter, reader);
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+        let name = markup::MAIN_VIEW_NAME.to_string();
+
+        let mut buffer = DataBindingBuffer::new(view, &lib.templates);
+        let mut context = DefaultContextManager::default();
+        let friends: Vec<HashMap<String, u32>> = (0..2).map(|i| {
+            let mut friend = HashMap::new();
+            friend.insert("score".to_string(), 10 + i);
+            friend
+        }).collect();
+        context.register_global_store("friends".to_string(), friends);
+
+        assert_eq!(buffer.enumerate_bindings().count(), 0);
+        assert!(buffer.update_iterators(&name, &context));
+        assert!(!buffer.update_iterators(&name, &context));
+        assert_eq!(buffer.iterator_lengths(), vec![2]);
+
+        let instance = markup::instantiate_repeats(view, &lib.templates,
+            &buffer.iterator_lengths());
+        buffer.set_instance(&instance);
+        assert!(buffer.update(&name, &context));
+
+        let texts: Vec<_> = buffer.enumerate_bindings().map(|(_, b)| b.text()).collect();
+        assert_eq!(texts, vec!["10".to_string(), "11".to_string()]);
+    }
+
+    #[test]
+    fn windows_should_rescope_the_repeat_bindings() {
+        let reader = BufReader::new(
+            "<view>\
+                <repeat iter=\"{{ auctions }}\" template-name=\"auction\" virtual=\"true\"/>\
+             </view>\
+             <template name=\"auction\">\
+                <button>{{ price }}</button>\
+             </template>".as_bytes());
+        let lib = markup::parse(EmptyErrorReporter, reader);
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+        let name = markup::MAIN_VIEW_NAME.to_string();
+
+        let mut buffer = DataBindingBuffer::new(view, &lib.templates);
+        let mut context = DefaultContextManager::default();
+        let auctions: Vec<HashMap<String, u32>> = (0..5).map(|i| {
+            let mut auction = HashMap::new();
+            auction.insert("price".to_string(), 10 + i);
+            auction
+        }).collect();
+        context.register_global_store("auctions".to_string(), auctions);
+        buffer.update_iterators(&name, &context);
+
+        // Two instances for five items.
+        let instance = markup::instantiate_repeats(view, &lib.templates, &[2]);
+        buffer.set_instance(&instance);
+        buffer.set_repeat_windows(&[RepeatWindow { first: 3, slots: 2 }]);
+        assert!(buffer.update(&name, &context));
+
+        let texts: Vec<_> = buffer.enumerate_bindings().map(|(_, b)| b.text()).collect();
+        assert_eq!(texts, vec!["14".to_string(), "13".to_string()]);
     }
 }
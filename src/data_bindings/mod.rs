@@ -10,6 +10,9 @@ pub use oil_databindings::{
     DefaultContextManager,
     DataBindingsContext
 };
+pub mod store {
+    pub use oil_databindings::store::{StoreValueStatic, AsStoreValue};
+}
 pub mod context {
     pub use oil_databindings::context::ContextManager;
 }
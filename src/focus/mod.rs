@@ -1,10 +1,10 @@
 use std::f32;
 use std::ptr;
 use std::mem;
-use std::ops::{Index, Deref};
+use std::ops::{Index, Deref, Range};
 
 use util::flat_tree::{FlatTree, TreeNode};
-use layout::{LayoutBuffer, Rect};
+use layout::{LayoutBuffer, LayoutBox, Rect};
 use markup::Node;
 use self::tagged_tree::TaggedNode;
 use std::default::Default;
@@ -37,6 +37,12 @@ impl FocusAcceptor {
         }
     }
 
+    /// Line of this node among its siblings, used to move
+    /// the focus up and down.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
     pub fn parent(&self) -> Option<&FocusNode> {
         if self.parent.is_null() {
             None
@@ -111,6 +117,16 @@ impl FocusBuffer {
         }
     }
 
+    /// Returns the acceptor at the given global index, if there is one.
+    pub fn acceptor_at(&self, global_index: usize) -> Option<FocusedElement> {
+        self.buffer.iter()
+            .find(|&a| a.is_acceptor && self.node_as_global_index(a) == global_index as isize)
+            .map(|node| FocusedElement {
+                focus_node: self.node_as_index(node),
+                cursor: Cursor::new(node),
+            })
+    }
+
     pub fn global_index(&self, el: &FocusedElement) -> Option<usize> {
         if el.focus_node >= 0 {
             Some(self.node_as_global_index(self.get(el.focus_node as usize).unwrap()) as usize)
@@ -165,15 +181,7 @@ impl FocusBuffer {
             //
             // See RenderBuffer#update_nodes
             let boxi = unsafe { layout_data.get_unchecked(i) };
-            let ref rec = boxi.dim().content;
-            focus.bounds = Rect {
-                x: rec.x,
-                y: rec.y,
-                width: rec.width + boxi.dim().margin.left + boxi.dim().border.left
-                    + boxi.dim().margin.right + boxi.dim().border.right,
-                height: rec.height + boxi.dim().margin.top + boxi.dim().border.top
-                    + boxi.dim().margin.bottom + boxi.dim().border.bottom,
-            }
+            focus.bounds = bounds_of(boxi);
         }
 
         // Resolve line numbers
@@ -181,6 +189,47 @@ impl FocusBuffer {
             resolve_line_numbers(node);
         }
     }
+
+    /// Same as `update_nodes`, for the nodes of the given subtrees
+    /// only. The root of each subtree must have kept its position.
+    pub fn update_nodes_in(&mut self, layout_data: &LayoutBuffer, subtrees: &[Range<usize>]) {
+
+        for subtree in subtrees {
+
+            for (&i, focus) in self.buffer.enumerate_lookup_indices_in_mut(subtree.clone()).unwrap() {
+                focus.bounds = bounds_of(&layout_data[i]);
+            }
+
+            // Only the lines inside the nearest focus node enclosing the
+            // subtree can have changed: its root if it is a focus node,
+            // the parent of its first focus node otherwise.
+            let first = self.buffer.enumerate_lookup_indices_in_mut(subtree.clone()).unwrap()
+                .next()
+                .map(|(&i, node)| (i, node as *const FocusNode, node.parent));
+
+            let enclosing = match first {
+                Some((i, node, _)) if i == subtree.start => node,
+                Some((_, _, parent)) if !parent.is_null() => parent,
+                // No focus node, or only roots of the focus tree.
+                _ => continue,
+            };
+
+            let index = self.buffer.node_as_index(unsafe { &*enclosing }) as usize;
+            resolve_line_numbers(&mut self.buffer[index]);
+        }
+    }
+}
+
+fn bounds_of(boxi: &LayoutBox) -> Rect {
+    let ref rec = boxi.dim().content;
+    Rect {
+        x: rec.x,
+        y: rec.y,
+        width: rec.width + boxi.dim().margin.left + boxi.dim().border.left
+            + boxi.dim().margin.right + boxi.dim().border.right,
+        height: rec.height + boxi.dim().margin.top + boxi.dim().border.top
+            + boxi.dim().margin.bottom + boxi.dim().border.bottom,
+    }
 }
 
 fn converter(tagged_node: &TaggedNode) -> Option<FocusAcceptor> {
@@ -224,3 +273,64 @@ fn resolve_line_numbers(focus_node: &mut FocusNode) {
     }
 
 }
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use super::FocusBuffer;
+    use std::io::BufReader;
+    use markup;
+    use style;
+    use layout::LayoutBuffer;
+    use state::StateBuffer;
+    use oil_shared::deps::StyleDefinitions;
+    use oil_parsers::StdOutErrorReporter;
+    use resource;
+
+    #[test]
+    fn focus_should_follow_the_rows_and_columns_of_a_grid() {
+        let mut fake_resource_manager = resource::create_null_manager();
+        let defs = StyleDefinitions::new();
+        let stylesheet = style::parse(
+            StdOutErrorReporter,
+            BufReader::new(".grid { layout: grid; width: 300px; grid-columns: 1fr 1fr 1fr; }\
+                            .item { width: 80px; height: 20px; }".as_bytes()),
+            &defs,
+            &mut fake_resource_manager);
+        let lib = markup::parse(StdOutErrorReporter, BufReader::new(
+            "<view>\
+                <group class=\"grid\">\
+                    <repeat iter=\"{{items}}\" template-name=\"item\"/>\
+                </group>\
+            </view>\
+            <template name=\"item\">\
+                <button class=\"item\"></button>\
+            </template>".as_bytes()));
+        let view = lib.views.values().next().unwrap();
+        let root = markup::instantiate_repeats(view, &lib.templates, &[5]);
+        let state_buffer = StateBuffer::new(&root, &stylesheet);
+
+        let mut layout_buffer = LayoutBuffer::new(&root);
+        layout_buffer.update_from_state(&state_buffer);
+        layout_buffer.compute_layout(800.0, 600.0);
+
+        let mut focus = FocusBuffer::new(&root);
+        focus.update_nodes(&layout_buffer);
+
+        // The buttons are at 4, 6, 8 on the first row and 10, 12 below.
+        let first = focus.first_acceptor();
+        let right = focus.focus_right(&first).unwrap();
+        let below = focus.focus_down(&right).unwrap();
+        assert_eq!(focus.global_index(&first), Some(4));
+        assert_eq!(focus.global_index(&right), Some(6));
+        assert_eq!(focus.global_index(&below), Some(12));
+
+        // The instances around the buttons don't accept the focus.
+        assert_eq!(focus.acceptor_at(8).and_then(|el| focus.global_index(&el)), Some(8));
+        assert!(focus.acceptor_at(7).is_none());
+    }
+}
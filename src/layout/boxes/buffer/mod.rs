@@ -1,23 +1,53 @@
 use util::flat_tree::FlatTree;
 use util::flat_tree::TreeNode;
-use std::ops::Deref;
+use util::{F32Ord, parent_indices, subtree_ends, merge_subtrees};
+use std::ops::{Deref, Range};
+use std::f32;
 use super::LayoutBox;
-use markup::Node;
+use layout::{Length, Rect, Units, Flex, FlexDirection, AlignItems, Grid, Overflow};
+use layout::{Scroll, VirtualRepeat};
+use layout::flex::{grow_or_shrink, justify_offsets, align_offset};
+use layout::grid::{resolve_tracks, track_offsets};
+use layout::scroll::offset_to_show;
+use oil_shared::properties::PropertyName;
+use markup::{Node, NodeType, RepeatData, RepeatWindow};
 use state::StateBuffer;
+use text::TextBuffer;
 
 
-mod repeat_node;
 mod simple_node;
 
-pub struct LayoutBuffer(FlatTree<LayoutBox>);
+pub struct LayoutBuffer {
+    tree: FlatTree<LayoutBox>,
+    // `root-unit` of the view root.
+    root_unit: Option<Length>,
+    // Global indices in the order the nodes are painted.
+    paint_order: Vec<usize>,
+    // Clip rectangles applied while painting.
+    clips: Vec<Clip>,
+    // Global index of the parent of each node.
+    parents: Vec<Option<usize>>,
+    // Global index following the last descendant of each node.
+    ends: Vec<usize>,
+}
 pub type LayoutNode = TreeNode<LayoutBox>;
 
+/// Clip rectangle of a node with `overflow: hidden | scroll`.
+/// It applies to the nodes of `paint_order` from `start` (included)
+/// to `end` (excluded), which are the descendants of that node.
+#[derive(Copy, Clone, Debug)]
+pub struct Clip {
+    pub start: usize,
+    pub end: usize,
+    pub rect: Rect,
+}
+
 
 impl Deref for LayoutBuffer {
     type Target = [LayoutNode];
 
     fn deref<'a>(&'a self) -> &'a [LayoutNode] {
-        self.0.deref()
+        self.tree.deref()
     }
 }
 
@@ -26,38 +56,485 @@ impl LayoutBuffer {
     pub fn new(style_tree: &Node) -> LayoutBuffer {
 
         let size = style_tree.tree_size();
-
-        LayoutBuffer(FlatTree::new(style_tree, size, converter))
+        let parents = parent_indices(style_tree);
+
+        LayoutBuffer {
+            tree: FlatTree::new(style_tree, size, converter),
+            root_unit: None,
+            paint_order: (0..size).collect(),
+            clips: Vec::new(),
+            ends: subtree_ends(&parents),
+            parents: parents,
+        }
     }
 
     pub fn update_from_state(&mut self, state_buffer: &StateBuffer) {
 
-        for (lb, state) in self.0.iter_mut().zip(state_buffer.iter()) {
+        for (lb, state) in self.tree.iter_mut().zip(state_buffer.iter()) {
             lb.update_from_state(state);
         }
+
+        self.root_unit = state_buffer.iter().next()
+            .and_then(|root| root.length_prop_as_opt(PropertyName::ROOT_UNIT));
+    }
+
+    /// Text nodes are sized by their text.
+    /// Must be called after `update_from_state`.
+    pub fn update_from_text(&mut self, text_buffer: &TextBuffer) {
+
+        for (&i, text) in text_buffer.enumerate_lookup_indices() {
+            self.tree[i].set_text(text.metrics().clone(), !text.is_left_aligned());
+        }
     }
 
     pub fn compute_layout(&mut self, max_width: f32, max_height: f32) {
 
+        let units = Units::new(max_width, max_height, self.root_unit);
+
+        // Sizes given by the stacks are computed again.
+        for lb in self.tree.iter_mut() {
+            lb.flex_width = None;
+            lb.flex_height = None;
+            lb.dirty = false;
+        }
+
         // First pass: compute default width
-        for root in self.0.tree_iter_mut() {
-            compute_layout_defaut_width(root, max_width);
+        for root in self.tree.tree_iter_mut() {
+            compute_layout_defaut_width(root, &units, max_width, max_width, false);
         }
 
         // Second pass: compute auto margins and width auto
-        for root in self.0.tree_iter_mut() {
+        for root in self.tree.tree_iter_mut() {
             compute_layout_auto_width(root, max_width);
         }
 
         // Third pass: layout children and compute their height
-        for root in self.0.tree_iter_mut() {
-            compute_layout_height_and_position(root, max_height);
+        for root in self.tree.tree_iter_mut() {
+            compute_layout_height_and_position(root, &units, max_height, max_height, false);
+        }
+
+        self.update_paint_order();
+    }
+
+    /// Mark the node at the given global index, and its descendants,
+    /// to be laid out again by `compute_dirty_layout`.
+    pub fn mark_dirty(&mut self, index: usize) {
+        self.tree[index].dirty = true;
+    }
+
+    /// Global indices of the node at the given
+    /// global index and of its descendants.
+    pub fn subtree(&self, index: usize) -> Range<usize> {
+        index..self.ends[index]
+    }
+
+    /// Subtrees of the nodes marked dirty, sorted. A dirty
+    /// node inside the subtree of another one is left out.
+    pub fn dirty_subtrees(&self) -> Vec<Range<usize>> {
+
+        let mut subtrees = Vec::new();
+        let mut i = 0;
+
+        while i < self.tree.len() {
+            if self.tree[i].dirty {
+                subtrees.push(self.subtree(i));
+                i = self.ends[i];
+            } else {
+                i += 1;
+            }
+        }
+
+        subtrees
+    }
+
+    /// Read again the style of the dirty subtrees and lay them out.
+    /// Their ancestors are laid out again as long as their size changes,
+    /// the rest of the view is left untouched. Returns the subtrees laid
+    /// out again, sorted. Must be called after a first `compute_layout`.
+    pub fn compute_dirty_layout(
+        &mut self,
+        state_buffer: &StateBuffer,
+        text_buffer: &TextBuffer,
+        max_width: f32,
+        max_height: f32) -> Vec<Range<usize>>
+    {
+        let dirty = self.dirty_subtrees();
+
+        // Reading the style resets the dimensions,
+        // keep where the subtrees were.
+        let before: Vec<Rect> = dirty.iter()
+            .map(|subtree| self.tree[subtree.start].dim.margin_box())
+            .collect();
+
+        for subtree in dirty.iter() {
+            for i in subtree.clone() {
+                self.tree[i].update_from_state(&state_buffer[i]);
+                self.tree[i].dirty = false;
+            }
+
+            for (&i, text) in text_buffer.enumerate_lookup_indices_in(subtree.clone()) {
+                self.tree[i].set_text(text.metrics().clone(), !text.is_left_aligned());
+            }
+        }
+
+        if dirty.first().map_or(false, |subtree| subtree.start == 0) {
+            self.root_unit = state_buffer[0].length_prop_as_opt(PropertyName::ROOT_UNIT);
+        }
+
+        let units = Units::new(max_width, max_height, self.root_unit);
+        let mut laid_out: Vec<Range<usize>> = Vec::new();
+
+        for (subtree, before) in dirty.into_iter().zip(before.into_iter()) {
+
+            // Already laid out along with an ancestor.
+            if laid_out.iter().any(|l| l.start <= subtree.start && subtree.end <= l.end) {
+                continue;
+            }
+
+            let root = self.relayout(subtree.start, before, &units);
+            laid_out.push(self.subtree(root));
+        }
+
+        self.update_paint_order();
+        merge_subtrees(laid_out)
+    }
+
+    /// Global indices of the nodes in the order they must be painted.
+    /// This is the order of the markup, except for absolute nodes which
+    /// are painted after their siblings sorted by `z-index`.
+    pub fn paint_order(&self) -> &[usize] {
+        &self.paint_order
+    }
+
+    /// Clip rectangles sorted by `start`. When two of
+    /// them overlap, one contains the other.
+    pub fn clips(&self) -> &[Clip] {
+        &self.clips
+    }
+
+    /// Scroll the ancestors of the node at the given global index
+    /// so that it is visible in their content box. Returns the global
+    /// index of the outermost ancestor that scrolled, if any.
+    /// Must be called after `compute_layout`.
+    pub fn scroll_into_view(&mut self, index: usize) -> Option<usize> {
+
+        let mut parent = self.parents[index];
+        let mut scrolled = None;
+
+        while let Some(p) = parent {
+            if self.tree[p].overflow == Overflow::Scroll {
+                let target = self.tree[index].dim.margin_box();
+                let view = self.tree[p].dim.content_box();
+                let dx = offset_to_show(target.x, target.width, view.x, view.width);
+                let dy = offset_to_show(target.y, target.height, view.y, view.height);
+                if self.scroll_by(p, dx, dy) {
+                    scrolled = Some(p);
+                }
+            }
+            parent = self.parents[p];
         }
+
+        scrolled
+    }
+
+    /// Global index of the nearest ancestor of the given node with
+    /// `overflow: scroll`, or of the first such node of the view.
+    pub fn scroll_container(&self, index: Option<usize>) -> Option<usize> {
+
+        let mut parent = index.and_then(|i| self.parents[i]);
+
+        while let Some(p) = parent {
+            if self.tree[p].overflow == Overflow::Scroll {
+                return Some(p);
+            }
+            parent = self.parents[p];
+        }
+
+        self.tree.iter().position(|lb| lb.overflow == Overflow::Scroll)
+    }
+
+    /// Move the scroll offset of the node at the given global index,
+    /// along with its children. Returns false if it didn't move.
+    pub fn scroll_by(&mut self, index: usize, dx: f32, dy: f32) -> bool {
+
+        let (dx, dy) = self.tree[index].scroll.scroll_by(dx, dy);
+
+        if dx == 0f32 && dy == 0f32 {
+            return false;
+        }
+
+        for child in self.tree[index].children_mut() {
+            move_subtree_x(child, -dx);
+            move_subtree_y(child, -dy);
+        }
+
+        // The clips of the descendants move with them. The node of a
+        // clip is the one painted just before it starts.
+        let subtree = self.subtree(index);
+        for clip in self.clips.iter_mut() {
+            let node = self.paint_order[clip.start - 1];
+            if node > subtree.start && node < subtree.end {
+                clip.rect = self.tree[node].dim.content_box();
+            }
+        }
+
+        true
+    }
+
+    /// Global index and scroll offset of the nodes that scrolled.
+    pub fn scroll_offsets(&self) -> Vec<(usize, Scroll)> {
+        self.tree.iter().enumerate()
+            .filter(|&(_, lb)| lb.scroll.x != 0f32 || lb.scroll.y != 0f32)
+            .map(|(i, lb)| (i, lb.scroll))
+            .collect()
+    }
+
+    /// Set the scroll offset of the node at the given global
+    /// index. It is applied by the next layout.
+    pub fn set_scroll_offset(&mut self, index: usize, scroll: Scroll) {
+        self.tree[index].scroll = scroll;
+    }
+
+    /// Give their window to the repeats, with the number of items of
+    /// each one. Both are in the order of `markup::repeat_iterators`.
+    /// A virtual repeat whose window moved is marked dirty.
+    pub fn set_repeat_windows(&mut self, totals: &[usize], windows: &[RepeatWindow]) {
+
+        let repeats = self.tree.iter_mut().filter(|lb| lb.is_repeat);
+
+        for (lb, (&total, window)) in repeats.zip(totals.iter().zip(windows.iter())) {
+            let moved = match lb.virtual_repeat {
+                Some(ref mut virtual_repeat) => {
+                    let moved = virtual_repeat.total != total
+                        || virtual_repeat.first != window.first;
+                    virtual_repeat.total = total;
+                    virtual_repeat.first = window.first;
+                    moved
+                }
+                None => false,
+            };
+
+            if moved {
+                lb.dirty = true;
+            }
+        }
+    }
+
+    /// Returns the windows that show the visible items of the virtual
+    /// repeats, with `margin` more items on each side. The other repeats
+    /// keep their window. A window never loses slots, so that it can be
+    /// moved without instantiating the repeat again.
+    /// Must be called after `compute_layout`.
+    pub fn repeat_windows(&self, current: &[RepeatWindow], margin: usize) -> Vec<RepeatWindow> {
+
+        let repeats = self.tree.iter().enumerate().filter(|&(_, lb)| lb.is_repeat);
+
+        repeats.zip(current.iter())
+            .map(|((i, lb), &window)| match lb.virtual_repeat {
+                Some(ref v) if v.item_height > 0f32 => {
+                    let view = self.visible_area(i);
+                    let top = view.y - lb.dim.content.y;
+                    let first = (top / v.item_height).floor().max(0f32) as usize;
+                    let last = ((top + view.height) / v.item_height).ceil().max(0f32) as usize;
+                    let slots = (last.saturating_sub(first) + 2 * margin)
+                        .max(window.slots)
+                        .min(v.total);
+
+                    RepeatWindow {
+                        first: first.saturating_sub(margin).min(v.total - slots),
+                        slots: slots,
+                    }
+                }
+                // One instance is needed to know the height of a row.
+                Some(ref v) => RepeatWindow {
+                    first: window.first,
+                    slots: window.slots.max(1).min(v.total),
+                },
+                None => window,
+            })
+            .collect()
+    }
+
+    /// Content box of the nearest ancestor clipping the given
+    /// node, or of the root if there is none.
+    fn visible_area(&self, index: usize) -> Rect {
+
+        let mut parent = self.parents[index];
+
+        while let Some(p) = parent {
+            if self.tree[p].clips() {
+                return self.tree[p].dim.content_box();
+            }
+            parent = self.parents[p];
+        }
+
+        self.tree[0].dim.content_box()
+    }
+
+    /// Lay out again the node at the given global index, where it is and
+    /// in the space it was given by the last layout. `before` is its margin
+    /// box in that layout. The parent is laid out instead when the node
+    /// can't be on its own, and after it when its size changed.
+    /// Returns the global index of the last node laid out.
+    fn relayout(&mut self, index: usize, before: Rect, units: &Units) -> usize {
+
+        let mut index = index;
+        let mut before = before;
+
+        loop {
+
+            while !self.stands_alone(index) {
+                index = self.parents[index].unwrap();
+                before = self.tree[index].dim.margin_box();
+            }
+
+            // Where an absolute box is placed from.
+            let parent_content = self.parents[index].map(|p| {
+                let parent = &self.tree[p];
+                let mut content = parent.dim.content_box();
+                // The children were moved by the scroll offset.
+                content.x -= parent.scroll.x;
+                content.y -= parent.scroll.y;
+                content
+            });
+
+            // Scope to release the node before looking at its parent.
+            let (after, is_absolute) = {
+                let node = &mut self.tree[index];
+                let given = node.given;
+
+                compute_layout_defaut_width(
+                    node, units, given.space_for_self, given.parent_width, false);
+                compute_layout_auto_width(node, given.auto_space);
+
+                match parent_content {
+                    Some(content) if node.flags.is_absolute() => {
+                        compute_layout_absolute_position(node, units, content);
+                    }
+                    _ => {
+                        node.dim.content.x = before.x;
+                        node.dim.content.y = before.y;
+                        compute_layout_height_and_position(
+                            node, units, given.max_height, given.parent_height, false);
+                    }
+                }
+
+                (node.dim.margin_box(), node.flags.is_absolute())
+            };
+
+            // An absolute box doesn't take space in its parent.
+            if is_absolute {
+                return index;
+            }
+
+            match self.parents[index] {
+                Some(parent) if after.width != before.width
+                    || after.height != before.height =>
+                {
+                    index = parent;
+                    before = self.tree[index].dim.margin_box();
+                }
+                _ => return index,
+            }
+        }
+    }
+
+    /// Returns true if the node at the given global index can be laid
+    /// out again without its parent, as long as its size doesn't change.
+    fn stands_alone(&self, index: usize) -> bool {
+
+        let node = &self.tree[index];
+
+        match self.parents[index] {
+            // Placed in the content box of the parent,
+            // whatever the siblings are.
+            Some(_) if node.flags.is_absolute() => true,
+            Some(p) => {
+                let parent = &self.tree[p];
+
+                // Stacks, grids and repeats size or place their
+                // children from the sizes of all of them.
+                parent.flex.is_none()
+                    && parent.grid.is_none()
+                    && !parent.is_repeat
+                    // The parent wraps the text on its lines and resolves
+                    // the auto margins with the height of the line.
+                    && !node.is_text()
+                    && !node.flags.has_margin_top_or_bot_auto()
+            }
+            None => true,
+        }
+    }
+
+    fn update_paint_order(&mut self) {
+
+        let mut paint_order = Vec::with_capacity(self.tree.len());
+        let mut clips = Vec::new();
+        for root in self.tree.tree_iter() {
+            push_paint_order(&self.tree, root, &mut paint_order, &mut clips);
+        }
+        self.paint_order = paint_order;
+        self.clips = clips;
     }
 }
 
-fn converter(_: &Node) -> Option<LayoutBox> {
-    Some(LayoutBox::default())
+fn converter(node: &Node) -> Option<LayoutBox> {
+    let (is_repeat, virtual_repeat) = match node.node_type {
+        NodeType::Repeat(RepeatData { is_virtual: true, .. }) => {
+            (true, Some(VirtualRepeat::default()))
+        }
+        NodeType::Repeat(_) => (true, None),
+        _ => (false, None),
+    };
+
+    Some(LayoutBox {
+        is_repeat: is_repeat,
+        virtual_repeat: virtual_repeat,
+        .. LayoutBox::default()
+    })
+}
+
+fn push_paint_order(
+    tree: &FlatTree<LayoutBox>,
+    this: &LayoutNode,
+    order: &mut Vec<usize>,
+    clips: &mut Vec<Clip>)
+{
+    order.push(tree.node_as_index(this) as usize);
+
+    let clip = if this.clips() {
+        clips.push(Clip {
+            start: order.len(),
+            end: order.len(),
+            rect: this.dim.content_box(),
+        });
+        Some(clips.len() - 1)
+    } else {
+        None
+    };
+
+    for child in this.children().filter(|c| !c.flags.is_absolute()) {
+        push_paint_order(tree, child, order, clips);
+    }
+
+    // The sort is stable: same z-index, same order as the markup.
+    let mut absolutes: Vec<&LayoutNode> = this.children()
+        .filter(|c| c.flags.is_absolute())
+        .collect();
+    absolutes.sort_by(|a, b| F32Ord(a.z_index).cmp(&F32Ord(b.z_index)));
+
+    for child in absolutes {
+        push_paint_order(tree, child, order, clips);
+    }
+
+    if let Some(clip) = clip {
+        if clips[clip].start == order.len() {
+            // Nothing to clip.
+            clips.pop();
+        } else {
+            clips[clip].end = order.len();
+        }
+    }
 }
 
 /// This function compute the width for this node
@@ -65,8 +542,39 @@ fn converter(_: &Node) -> Option<LayoutBox> {
 /// than the one given.
 ///
 /// This can appear when this node has a child with a fixed width.
-fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self: f32) -> f32
+///
+/// With `measure`, only the width of this node is computed: the
+/// children of a stack don't get their final width. They are laid
+/// out once, when the stack itself is.
+fn compute_layout_defaut_width(
+    this: &mut LayoutNode,
+    units: &Units,
+    space_available_for_self: f32,
+    parent_width: f32,
+    measure: bool) -> f32
 {
+    this.given.space_for_self = space_available_for_self;
+    this.given.parent_width = parent_width;
+    this.resolve_horizontal(units, parent_width);
+
+    // The width given by a parent stack wins over the style.
+    if let Some(width) = this.flex_width {
+        this.dim.content.width = width;
+    }
+    let width_fixed = this.flags.has_width_fixed() || this.flex_width.is_some();
+    let flex = this.flex;
+    let grid = this.grid.clone();
+
+    // An absolute box doesn't share the line with its siblings,
+    // it takes the space between its offsets instead.
+    let space_available_for_self = if this.flags.is_absolute() {
+        parent_width
+            - this.offsets.left.unwrap_or(0f32)
+            - this.offsets.right.unwrap_or(0f32)
+    } else {
+        space_available_for_self
+    };
+
     // Compute the extra part to remove
     let mut o = this.dim.padding.left
         + this.dim.padding.right
@@ -76,10 +584,10 @@ fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self:
         + this.dim.border.right;
 
     // Compute the space available for each line
-    let space_available = if this.flags.has_width_fixed() {
+    let space_available = if width_fixed {
         this.dim.content.width
     } else {
-        space_available_for_self - o
+        this.clamp_width(space_available_for_self - o)
     };
 
     // Iterating variables
@@ -88,7 +596,11 @@ fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self:
     let mut line_space_available = space_available;
 
     // Scope to reduce iter lifetime.
-    {
+    if let Some(ref flex) = flex {
+        max = compute_flex_default_width(this, units, flex, space_available);
+    } else if let Some(ref grid) = grid {
+        max = compute_grid_default_width(this, units, grid, space_available, measure);
+    } else {
         let mut iter = this.children_mut();
         let mut option_next = iter.next();
         let mut next_child = false;
@@ -99,10 +611,27 @@ fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self:
             // child has the type &mut &mut LayoutBox (one additional indirection)
             // but in release both child, option_next and iter are optimized out.
             // So I guess I shouldn't worry about that, or not ?
+            // Absolute children are out of the line flow.
+            if option_next.as_ref().map_or(false, |c| c.flags.is_absolute()) {
+                if let Some(ref mut child) = option_next {
+                    compute_layout_defaut_width(
+                        child, units, space_available, space_available, measure);
+                }
+                option_next = iter.next();
+                continue;
+            }
+
             if let Some(ref mut child) = option_next {
 
-                // Recursive call: eat the space given
-                let space_eaten = compute_layout_defaut_width(child, line_space_available);
+                // Recursive call: eat the space given.
+                // Text is wrapped on the whole line width instead, so
+                // that it starts on a new line when it doesn't fit.
+                let space_eaten = if child.is_text() {
+                    child.layout_text(space_available)
+                } else {
+                    compute_layout_defaut_width(
+                        child, units, line_space_available, space_available, measure)
+                };
 
                 // If the child has not eaten more than given
                 // then we just reduce the space available for the next child
@@ -165,14 +694,29 @@ fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self:
     }
 
     // Assign width for this.
-    if !this.flags.has_width_fixed() {
+    if !width_fixed {
         this.dim.content.width = if this.flags.has_width_expand() {
             space_available
         } else {
-            max.min(space_available)
+            this.clamp_width(max.min(space_available))
         }
     };
 
+    // What doesn't fit can be scrolled.
+    if this.overflow == Overflow::Scroll {
+        this.scroll.max_x = (max - this.dim.content.width).max(0f32);
+    }
+
+    // Now that the width is known, stacked children can grow or shrink
+    // and grid items take the width of their column.
+    if let Some(ref flex) = flex {
+        if !measure {
+            compute_flex_final_width(this, units, flex);
+        }
+    } else if let Some(ref grid) = grid {
+        compute_grid_final_width(this, units, grid, measure);
+    }
+
     // Compute the free space for margin in expand mode:
     let s = space_available_for_self - o - this.dim.content.width;
 
@@ -194,10 +738,10 @@ fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self:
         _ => ()
     }
 
-    if this.flags.has_width_fixed() {
+    if width_fixed {
         this.dim.content.width + o
     } else {
-        max + o
+        this.clamp_width(max) + o
     }
 }
 
@@ -206,8 +750,19 @@ fn compute_layout_defaut_width(this: &mut LayoutNode, space_available_for_self:
 /// It should be called after compute_layout_default_width
 fn compute_layout_auto_width(this: &mut LayoutNode, space_available: f32)
 {
+    this.given.auto_space = space_available;
+
+    let space_available = if this.flags.is_absolute() {
+        space_available
+            - this.offsets.left.unwrap_or(0f32)
+            - this.offsets.right.unwrap_or(0f32)
+    } else {
+        space_available
+    };
+
     // Resolve auto width for this.
-    if this.flags.has_width_auto() {
+    // The width given by a parent stack is final.
+    if this.flags.has_width_auto() && this.flex_width.is_none() {
         let o = this.dim.padding.left
             + this.dim.padding.right
             + this.dim.margin.left
@@ -215,14 +770,26 @@ fn compute_layout_auto_width(this: &mut LayoutNode, space_available: f32)
             + this.dim.border.left
             + this.dim.border.right;
 
-        this.dim.content.width = space_available - o;
+        this.dim.content.width = this.clamp_width(space_available - o);
     }
 
+    // Stacks and grids wrap their text items on the width of the item.
+    let wraps_text = this.flex.is_none() && this.grid.is_none();
+
     for child in this.children_mut() {
 
+        // The width of this node is final, text is
+        // wrapped again if it grew or shrank since.
+        if wraps_text && child.is_text() {
+            child.layout_text(this.dim.content.width);
+        }
         compute_layout_auto_width(child, this.dim.content.width);
     }
 
+    if this.flex_width.is_some() {
+        return;
+    }
+
     // Compute the free space for margin in auto mode:
     let s = space_available - this.dim.content.width;
 
@@ -242,13 +809,46 @@ fn compute_layout_auto_width(this: &mut LayoutNode, space_available: f32)
     }
 }
 
-//
-// TODO: FIXME Text nodes must have their size precomputed somehow
 //
 // PRECONDITONS: compute_width has been called
 //
-fn compute_layout_height_and_position(this: &mut LayoutNode, max_height: f32)
+/// With `measure`, only the height of this node is computed: the
+/// children of a stack are not aligned nor resized, and absolute
+/// children are not placed.
+fn compute_layout_height_and_position(
+    this: &mut LayoutNode,
+    units: &Units,
+    max_height: f32,
+    parent_height: f32,
+    measure: bool)
 {
+    this.given.max_height = max_height;
+    this.given.parent_height = parent_height;
+    this.resolve_vertical(units, parent_height);
+
+    let extra_height = this.dim.padding.top
+        + this.dim.padding.bottom
+        + this.dim.margin.top
+        + this.dim.margin.bottom
+        + this.dim.border.top
+        + this.dim.border.bottom;
+
+    // Auto takes the height of the parent while expand
+    // only takes what is left below the previous lines.
+    // Neither is bounded in a node that clips its children.
+    if this.flags.has_height_auto() && parent_height.is_finite() {
+        this.dim.content.height = this.clamp_height((parent_height - extra_height).max(0f32));
+    } else if this.flags.has_height_expand() && max_height.is_finite() {
+        this.dim.content.height = this.clamp_height((max_height - extra_height).max(0f32));
+    }
+
+    // The height given by a parent stack wins over the style.
+    if let Some(height) = this.flex_height {
+        this.dim.content.height = height;
+    }
+    // The rows of a virtual repeat are only stacked for their width.
+    let flex = if this.virtual_repeat.is_some() { None } else { this.flex };
+    let grid = this.grid.clone();
 
     // At this point we don't know this.dim.height / this.dim.width
     // positions
@@ -262,26 +862,44 @@ fn compute_layout_height_and_position(this: &mut LayoutNode, max_height: f32)
         + this.dim.border.top;
 
     // Equivalent rule for child max height:
-    let child_max_height = if this.flags.has_height_fixed() {
+    let child_max_height = if this.flags.has_height_fixed()
+        || this.flags.has_height_auto()
+        || this.flags.has_height_expand()
+        || this.flex_height.is_some()
+    {
 
         this.dim.content.height
     } else {
 
-        max_height
-        - this.dim.padding.bottom - this.dim.padding.top
-        - this.dim.border.bottom  - this.dim.border.top
-        - this.dim.margin.bottom  - this.dim.margin.top
+        this.clamp_height(max_height - extra_height)
+    };
+
+    // The children of a node that clips them
+    // are not limited by its height.
+    let children_max_height = if this.clips() {
+        f32::INFINITY
+    } else {
+        child_max_height
     };
 
     // Current line width allow to track the layout progress
     // in the x direction while height allow to track the y direction
     let mut current_line_width  = 0f32;
     let mut current_line_height = 0f32;
-    let mut current_height_left = child_max_height;
+    let mut current_height_left = children_max_height;
     let mut accumulated_line_height = 0f32;
 
     // Reduced scope for stack (borrowck problem otherwise)
-    {
+    if let Some(ref flex) = flex {
+        accumulated_line_height =
+            compute_flex_height_and_position(this, units, flex, children_max_height);
+    } else if let Some(ref grid) = grid {
+        accumulated_line_height =
+            compute_grid_height_and_position(this, units, grid, children_max_height, measure);
+    } else if this.virtual_repeat.is_some() {
+        accumulated_line_height =
+            compute_virtual_height_and_position(this, units, children_max_height, measure);
+    } else {
         // Used for margin (top/bottom)
         let mut stack: Vec<&mut LayoutNode> = Vec::with_capacity(4);
 
@@ -336,6 +954,11 @@ fn compute_layout_height_and_position(this: &mut LayoutNode, max_height: f32)
 
         for child in this.children_mut() {
 
+            // Placed once the size of this node is known.
+            if child.flags.is_absolute() {
+                continue;
+            }
+
             let need_new_line = child.flags.is_new_line_forced();
 
             // Line return ?
@@ -355,6 +978,8 @@ fn compute_layout_height_and_position(this: &mut LayoutNode, max_height: f32)
 
             // Update the x position:
             let child_total_width = child.dim.content.width
+                + child.dim.padding.left
+                + child.dim.padding.right
                 + child.dim.margin.left
                 + child.dim.margin.right
                 + child.dim.border.left
@@ -363,12 +988,15 @@ fn compute_layout_height_and_position(this: &mut LayoutNode, max_height: f32)
             x += child_total_width;
             current_line_width += child_total_width;
 
-            compute_layout_height_and_position(child, current_height_left);
+            compute_layout_height_and_position(
+                child, units, current_height_left, child_max_height, measure);
 
 
             // Note: at this point child.margin (top, right) are either fixed
             // or zero (if they were auto). They will be computed in a later pass.
             current_line_height = current_line_height.max(child.dim.content.height
+                + child.dim.padding.top
+                + child.dim.padding.bottom
                 + child.dim.margin.top
                 + child.dim.margin.bottom
                 + child.dim.border.top
@@ -399,6 +1027,973 @@ fn compute_layout_height_and_position(this: &mut LayoutNode, max_height: f32)
 
 
     // Finally: the height !
-    this.dim.content.height =
-        this.dim.content.height.max(child_max_height.min(accumulated_line_height));
+    this.dim.content.height = this.clamp_height(
+        this.dim.content.height.max(child_max_height.min(accumulated_line_height)));
+
+    // Now that the height is known, stacked children can be aligned
+    // and grid items moved to their row.
+    if let Some(ref flex) = flex {
+        if !measure {
+            compute_flex_final_position(this, units, flex);
+        }
+    } else if let Some(ref grid) = grid {
+        compute_grid_final_position(this, units, grid, measure);
+    }
+
+    // Vertical expand margins take the height left.
+    let s = if max_height.is_finite() {
+        (max_height - extra_height - this.dim.content.height).max(0f32)
+    } else {
+        0f32
+    };
+    let margin_top = this.dim.margin.top;
+
+    match (this.flags.has_margin_top_expand(), this.flags.has_margin_bottom_expand()) {
+        (true, true) => {
+            this.dim.margin.top    = s / 2f32;
+            this.dim.margin.bottom = s / 2f32;
+        }
+        (true, false) => {
+            this.dim.margin.top = s;
+        }
+        (false, true) => {
+            this.dim.margin.bottom = s;
+        }
+        _ => ()
+    }
+
+    // The children were placed below the previous margin.
+    let offset_y = this.dim.margin.top - margin_top;
+    if offset_y != 0f32 {
+        for child in this.children_mut() {
+            move_subtree_y(child, offset_y);
+        }
+    }
+
+    // Absolute children are placed relative to the content box.
+    let content = this.dim.content_box();
+
+    if !measure {
+        for child in this.children_mut().filter(|c| c.flags.is_absolute()) {
+            compute_layout_absolute_position(child, units, content);
+        }
+    }
+
+    // The offset is kept, unless the content became smaller.
+    if this.overflow == Overflow::Scroll {
+        this.scroll.max_y = (accumulated_line_height - this.dim.content.height).max(0f32);
+        this.scroll.clamp();
+
+        let (x, y) = (this.scroll.x, this.scroll.y);
+        for child in this.children_mut() {
+            move_subtree_x(child, -x);
+            move_subtree_y(child, -y);
+        }
+    }
+}
+
+/// Place an absolute box using its offsets from the content box
+/// of its parent. Without offsets, the box is at the top left corner.
+fn compute_layout_absolute_position(this: &mut LayoutNode, units: &Units, parent: Rect)
+{
+    let total_width = this.dim.content.width
+        + this.dim.padding.left
+        + this.dim.padding.right
+        + this.dim.margin.left
+        + this.dim.margin.right
+        + this.dim.border.left
+        + this.dim.border.right;
+
+    this.dim.content.x = match (this.offsets.left, this.offsets.right) {
+        (Some(left), _) => parent.x + left,
+        (None, Some(right)) => parent.x + parent.width - right - total_width,
+        (None, None) => parent.x,
+    };
+    this.dim.content.y = parent.y;
+
+    // The vertical offsets are only known once resolved,
+    // the box is moved afterward.
+    compute_layout_height_and_position(this, units, parent.height, parent.height, false);
+
+    let extra_height = this.dim.padding.top
+        + this.dim.padding.bottom
+        + this.dim.margin.top
+        + this.dim.margin.bottom
+        + this.dim.border.top
+        + this.dim.border.bottom;
+
+    let offset_y = match (this.offsets.top, this.offsets.bottom) {
+        (Some(top), Some(bottom)) => {
+            // Stretched between the two offsets.
+            if !this.flags.has_height_fixed() {
+                this.dim.content.height = (parent.height - top - bottom - extra_height)
+                    .max(0f32);
+            }
+            top
+        }
+        (Some(top), None) => top,
+        (None, Some(bottom)) => {
+            parent.height - bottom - this.dim.content.height - extra_height
+        }
+        (None, None) => 0f32,
+    };
+
+    move_subtree_y(this, offset_y);
+}
+
+fn move_subtree_x(this: &mut LayoutNode, offset_x: f32)
+{
+    this.dim.content.x += offset_x;
+
+    for child in this.children_mut() {
+        move_subtree_x(child, offset_x);
+    }
+}
+
+fn move_subtree_y(this: &mut LayoutNode, offset_y: f32)
+{
+    this.dim.content.y += offset_y;
+
+    for child in this.children_mut() {
+        move_subtree_y(child, offset_y);
+    }
+}
+
+/// Default width of the children of a stack. Each child is given
+/// the whole width available and only measured. Returns the width
+/// the stack would take to show its children without shrinking them.
+fn compute_flex_default_width(
+    this: &mut LayoutNode,
+    units: &Units,
+    flex: &Flex,
+    space_available: f32) -> f32
+{
+    let gap = flex.gap.resolve(units, space_available);
+    let mut widths = Vec::new();
+
+    for child in this.children_mut() {
+        child.flex_width = None;
+        if child.flags.is_absolute() {
+            compute_layout_defaut_width(child, units, space_available, space_available, false);
+        } else if child.is_text() {
+            widths.push(child.layout_text(space_available));
+        } else {
+            widths.push(compute_layout_defaut_width(
+                child, units, space_available, space_available, true));
+        }
+    }
+
+    match flex.direction {
+        FlexDirection::Row => {
+            let gaps = gap * (widths.len() as f32 - 1f32).max(0f32);
+            widths.iter().fold(gaps, |t, &w| t + w)
+        }
+        FlexDirection::Column => {
+            widths.iter().fold(0f32, |m, &w| m.max(w))
+        }
+    }
+}
+
+/// Give their final width to the children of a stack: they grow or
+/// shrink in a row and are stretched in a column. Each child is
+/// laid out once, with its final width.
+fn compute_flex_final_width(this: &mut LayoutNode, units: &Units, flex: &Flex)
+{
+    let width = this.dim.content.width;
+    let mut children: Vec<&mut LayoutNode> = this.children_mut()
+        .filter(|c| !c.flags.is_absolute())
+        .collect();
+
+    let widths: Vec<f32> = match flex.direction {
+        FlexDirection::Row => {
+            let gap = flex.gap.resolve(units, width);
+            let outer: Vec<f32> = children.iter().map(|c| c.dim.margin_box().width).collect();
+            let factors: Vec<(f32, f32)> = children.iter().map(|c| (c.grow, c.shrink)).collect();
+            let gaps = gap * (children.len() as f32 - 1f32).max(0f32);
+            let free = outer.iter().fold(width - gaps, |t, &w| t - w);
+
+            grow_or_shrink(&outer, &factors, free)
+        }
+        FlexDirection::Column => {
+            children.iter()
+                .map(|c| {
+                    let outer = c.dim.margin_box().width;
+                    let stretched = flex.align_items == AlignItems::Stretch
+                        && !c.is_text()
+                        && !c.flags.has_width_fixed();
+                    if stretched { width } else { outer }
+                })
+                .collect()
+        }
+    };
+
+    for (child, outer) in children.iter_mut().zip(widths.into_iter()) {
+        let extra = child.dim.margin_box().width - child.dim.content.width;
+        let content_width = (outer - extra).max(0f32);
+
+        if !child.is_text() {
+            child.flex_width = Some(content_width);
+            compute_layout_defaut_width(child, units, outer, width, false);
+        } else if content_width != child.dim.content.width {
+            child.layout_text(content_width);
+            child.dim.content.width = content_width;
+        }
+    }
+}
+
+/// Position the children of a stack on its main axis and measure
+/// their height. Returns the height the stack would take to show
+/// its children without shrinking them.
+fn compute_flex_height_and_position(
+    this: &mut LayoutNode,
+    units: &Units,
+    flex: &Flex,
+    child_max_height: f32) -> f32
+{
+    let content = this.dim.content_box();
+    let mut children: Vec<&mut LayoutNode> = this.children_mut()
+        .filter(|c| !c.flags.is_absolute())
+        .collect();
+
+    match flex.direction {
+        FlexDirection::Row => {
+            let gap = flex.gap.resolve(units, content.width);
+            let widths: Vec<f32> = children.iter().map(|c| c.dim.margin_box().width).collect();
+            let offsets = justify_offsets(flex.justify_content, &widths, gap, content.width);
+            let mut height = 0f32;
+
+            for (child, x) in children.iter_mut().zip(offsets.into_iter()) {
+                child.flex_height = None;
+                child.dim.content.x = content.x + x;
+                child.dim.content.y = content.y;
+                compute_layout_height_and_position(
+                    child, units, child_max_height, child_max_height, true);
+                height = height.max(child.dim.margin_box().height);
+            }
+
+            height
+        }
+        FlexDirection::Column => {
+            let gap = flex.gap.resolve(units, child_max_height);
+            let mut height = 0f32;
+
+            for (i, child) in children.iter_mut().enumerate() {
+                if i > 0 {
+                    height += gap;
+                }
+                let width = child.dim.margin_box().width;
+                child.flex_height = None;
+                child.dim.content.x = content.x
+                    + align_offset(flex.align_items, width, content.width);
+                child.dim.content.y = content.y + height;
+                compute_layout_height_and_position(
+                    child, units, (child_max_height - height).max(0f32), child_max_height, true);
+                height += child.dim.margin_box().height;
+            }
+
+            height
+        }
+    }
+}
+
+/// Once the height of a stack is known, align its children on
+/// the cross axis of a row, or make them grow or shrink and
+/// distribute them on the main axis of a column. Each child is
+/// laid out once, with its final height.
+fn compute_flex_final_position(this: &mut LayoutNode, units: &Units, flex: &Flex)
+{
+    let content = this.dim.content_box();
+    let mut children: Vec<&mut LayoutNode> = this.children_mut()
+        .filter(|c| !c.flags.is_absolute())
+        .collect();
+
+    // Only the children without a height are resized.
+    let is_resizable = |c: &LayoutNode| {
+        !c.is_text()
+            && !c.flags.has_height_fixed()
+            && !c.flags.has_height_auto()
+            && !c.flags.has_height_expand()
+    };
+
+    match flex.direction {
+        FlexDirection::Row => {
+            for child in children.iter_mut() {
+                let outer = child.dim.margin_box().height;
+
+                if flex.align_items == AlignItems::Stretch && is_resizable(child) {
+                    let extra = outer - child.dim.content.height;
+                    child.flex_height = Some((content.height - extra).max(0f32));
+                    child.dim.content.y = content.y;
+                    compute_layout_height_and_position(
+                        child, units, content.height, content.height, false);
+                } else {
+                    let given = child.given;
+                    compute_layout_height_and_position(
+                        child, units, given.max_height, given.parent_height, false);
+                    let offset_y = align_offset(flex.align_items, outer, content.height);
+                    move_subtree_y(child, offset_y);
+                }
+            }
+        }
+        FlexDirection::Column => {
+            let gap = flex.gap.resolve(units, content.height);
+            let outer: Vec<f32> = children.iter().map(|c| c.dim.margin_box().height).collect();
+            let factors: Vec<(f32, f32)> = children.iter()
+                .map(|c| if is_resizable(c) { (c.grow, c.shrink) } else { (0f32, 0f32) })
+                .collect();
+            let gaps = gap * (children.len() as f32 - 1f32).max(0f32);
+            let free = outer.iter().fold(content.height - gaps, |t, &h| t - h);
+            let heights = grow_or_shrink(&outer, &factors, free);
+            let offsets = justify_offsets(flex.justify_content, &heights, gap, content.height);
+
+            for ((child, height), y) in children.iter_mut()
+                .zip(heights.into_iter())
+                .zip(offsets.into_iter())
+            {
+                let outer = child.dim.margin_box().height;
+                if height != outer {
+                    let extra = outer - child.dim.content.height;
+                    child.flex_height = Some((height - extra).max(0f32));
+                    compute_layout_height_and_position(
+                        child, units, content.height, content.height, false);
+                } else {
+                    let given = child.given;
+                    compute_layout_height_and_position(
+                        child, units, given.max_height, given.parent_height, false);
+                }
+                let offset_y = content.y + y - child.dim.content.y;
+                move_subtree_y(child, offset_y);
+            }
+        }
+    }
+}
+
+/// Place the instances of a virtual repeat on the row of the item
+/// they show. Returns the height of all the rows, instantiated or not.
+fn compute_virtual_height_and_position(
+    this: &mut LayoutNode,
+    units: &Units,
+    child_max_height: f32,
+    measure: bool) -> f32
+{
+    let content = this.dim.content_box();
+    let mut virtual_repeat = this.virtual_repeat.unwrap_or(VirtualRepeat::default());
+
+    // Scope to release the children before storing the row height.
+    virtual_repeat.item_height = {
+        let mut children: Vec<&mut LayoutNode> = this.children_mut()
+            .filter(|c| !c.flags.is_absolute())
+            .collect();
+        let window = RepeatWindow {
+            first: virtual_repeat.first,
+            slots: children.len(),
+        };
+        let mut item_height = 0f32;
+
+        for child in children.iter_mut() {
+            child.dim.content.x = content.x;
+            child.dim.content.y = content.y;
+            compute_layout_height_and_position(
+                child, units, child_max_height, child_max_height, measure);
+            item_height = item_height.max(child.dim.margin_box().height);
+        }
+
+        for (slot, child) in children.iter_mut().enumerate() {
+            move_subtree_y(child, window.item_of_slot(slot) as f32 * item_height);
+        }
+
+        item_height
+    };
+
+    this.virtual_repeat = Some(virtual_repeat);
+    virtual_repeat.item_height * virtual_repeat.total as f32
+}
+
+/// Items of a grid in the order they fill the cells: the children
+/// in the flow, where a repeat is replaced by its instances.
+fn grid_items<'a>(this: &'a mut LayoutNode) -> Vec<&'a mut LayoutNode>
+{
+    let mut items = Vec::new();
+
+    for child in this.children_mut().filter(|c| !c.flags.is_absolute()) {
+        if child.is_repeat && child.virtual_repeat.is_none() {
+            items.extend(child.children_mut().filter(|c| !c.flags.is_absolute()));
+        } else {
+            items.push(child);
+        }
+    }
+
+    items
+}
+
+/// Default width of the items of a grid. Each item is given the
+/// whole width available. Returns the width the grid would take
+/// with columns as large as their largest item.
+fn compute_grid_default_width(
+    this: &mut LayoutNode,
+    units: &Units,
+    grid: &Grid,
+    space_available: f32,
+    measure: bool) -> f32
+{
+    for child in this.children_mut().filter(|c| c.flags.is_absolute()) {
+        compute_layout_defaut_width(child, units, space_available, space_available, measure);
+    }
+
+    let columns = grid.column_tracks();
+    let mut contents = vec![0f32; columns.len()];
+
+    for (i, item) in grid_items(this).into_iter().enumerate() {
+        item.flex_width = None;
+        let width = if item.is_text() {
+            item.layout_text(space_available)
+        } else {
+            compute_layout_defaut_width(item, units, space_available, space_available, measure)
+        };
+        let column = i % columns.len();
+        contents[column] = contents[column].max(width);
+    }
+
+    let gap = grid.gap.resolve(units, space_available);
+    let sizes = resolve_tracks(&columns, &contents, units, space_available, gap, None);
+    let gaps = gap * (sizes.len() as f32 - 1f32).max(0f32);
+
+    sizes.iter().fold(gaps, |t, &w| t + w)
+}
+
+/// Resolve the columns of a grid now that its width is known, and
+/// give their width to the items. Items without a fixed width are
+/// stretched to their column and laid out again.
+fn compute_grid_final_width(this: &mut LayoutNode, units: &Units, grid: &Grid, measure: bool)
+{
+    let width = this.dim.content.width;
+
+    for child in this.children_mut().filter(|c| c.is_repeat) {
+        child.flex_width = Some(width);
+        child.dim.content.width = width;
+    }
+
+    // Scope to release the items before storing the columns.
+    let columns = {
+        let mut items = grid_items(this);
+        let tracks = grid.column_tracks();
+        let mut contents = vec![0f32; tracks.len()];
+
+        for (i, item) in items.iter().enumerate() {
+            let column = i % tracks.len();
+            contents[column] = contents[column].max(item.dim.margin_box().width);
+        }
+
+        let gap = grid.gap.resolve(units, width);
+        let columns = resolve_tracks(&tracks, &contents, units, width, gap, Some(width));
+
+        for (i, item) in items.iter_mut().enumerate() {
+            let outer = columns[i % columns.len()];
+            let extra = item.dim.margin_box().width - item.dim.content.width;
+            let content_width = (outer - extra).max(0f32);
+
+            if item.is_text() {
+                if item.dim.content.width > content_width {
+                    item.layout_text(content_width);
+                    item.dim.content.width = content_width;
+                }
+            } else if item.flags.has_width_fixed() {
+                continue;
+            } else if content_width == item.dim.content.width {
+                item.flex_width = Some(content_width);
+            } else {
+                item.flex_width = Some(content_width);
+                compute_layout_defaut_width(item, units, outer, width, measure);
+            }
+        }
+
+        columns
+    };
+
+    this.grid_columns = columns;
+}
+
+/// Place the items of a grid in their cell, row after row, and
+/// compute their height. Returns the height the grid would take
+/// with rows as high as their highest item.
+fn compute_grid_height_and_position(
+    this: &mut LayoutNode,
+    units: &Units,
+    grid: &Grid,
+    child_max_height: f32,
+    measure: bool) -> f32
+{
+    let content = this.dim.content_box();
+    let column_gap = grid.gap.resolve(units, content.width);
+    let row_gap = grid.gap.resolve(units, child_max_height);
+    let offsets = track_offsets(&this.grid_columns, column_gap);
+    let mut items = grid_items(this);
+    let mut contents = Vec::new();
+    let mut height = 0f32;
+
+    for (r, row) in items.chunks_mut(offsets.len()).enumerate() {
+        if r > 0 {
+            height += row_gap;
+        }
+        let mut row_height = 0f32;
+
+        for (item, &x) in row.iter_mut().zip(offsets.iter()) {
+            item.flex_height = None;
+            item.dim.content.x = content.x + x;
+            item.dim.content.y = content.y + height;
+            compute_layout_height_and_position(
+                item, units, (child_max_height - height).max(0f32), child_max_height, measure);
+            row_height = row_height.max(item.dim.margin_box().height);
+        }
+
+        contents.push(row_height);
+        height += row_height;
+    }
+
+    let sizes = resolve_tracks(
+        &grid.row_tracks(contents.len()), &contents, units, child_max_height, row_gap, None);
+    let gaps = row_gap * (sizes.len() as f32 - 1f32).max(0f32);
+
+    sizes.iter().fold(gaps, |t, &h| t + h)
+}
+
+/// Once the height of a grid is known, resolve its rows and move
+/// the items to their row. Items without a height are stretched
+/// to the height of their row.
+fn compute_grid_final_position(this: &mut LayoutNode, units: &Units, grid: &Grid, measure: bool)
+{
+    let content = this.dim.content_box();
+
+    // A repeat covers the cells of its instances.
+    for child in this.children_mut().filter(|c| c.is_repeat) {
+        child.dim.content = content;
+    }
+
+    let columns = this.grid_columns.len();
+    let mut items = grid_items(this);
+    let mut contents = Vec::new();
+
+    for row in items.chunks(columns) {
+        contents.push(row.iter().fold(0f32, |m, c| m.max(c.dim.margin_box().height)));
+    }
+
+    let gap = grid.gap.resolve(units, content.height);
+    let rows = resolve_tracks(
+        &grid.row_tracks(contents.len()), &contents, units,
+        content.height, gap, Some(content.height));
+    let offsets = track_offsets(&rows, gap);
+
+    for (i, item) in items.iter_mut().enumerate() {
+        let row = rows[i / columns];
+        let y = content.y + offsets[i / columns];
+        let outer = item.dim.margin_box().height;
+        let resizable = !item.is_text()
+            && !item.flags.has_height_fixed()
+            && !item.flags.has_height_auto()
+            && !item.flags.has_height_expand();
+
+        if resizable && outer != row {
+            let extra = outer - item.dim.content.height;
+            item.flex_height = Some((row - extra).max(0f32));
+            item.dim.content.y = y;
+            compute_layout_height_and_position(item, units, row, row, measure);
+        } else {
+            let offset_y = y - item.dim.content.y;
+            move_subtree_y(item, offset_y);
+        }
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use super::LayoutBuffer;
+    use std::io::BufReader;
+    use markup;
+    use style;
+    use state::StateBuffer;
+    use text::{TextBuffer, TextMetrics, WhiteSpace};
+    use oil_shared::deps::StyleDefinitions;
+    use oil_shared::style::SelectorState;
+    use oil_parsers::StdOutErrorReporter;
+    use resource;
+
+    fn layout(st: &str, mk: &str) -> LayoutBuffer {
+        layout_with_repeats(st, mk, &[])
+    }
+
+    /// `lengths` gives the number of instances of each repeat.
+    fn layout_with_repeats(st: &str, mk: &str, lengths: &[usize]) -> LayoutBuffer {
+        buffers(st, mk, lengths).2
+    }
+
+    fn buffers(st: &str, mk: &str, lengths: &[usize])
+        -> (StateBuffer, TextBuffer, LayoutBuffer)
+    {
+        let mut fake_resource_manager = resource::create_null_manager();
+        let defs = StyleDefinitions::new();
+        let stylesheet = style::parse(
+            StdOutErrorReporter,
+            BufReader::new(st.as_bytes()),
+            &defs,
+            &mut fake_resource_manager);
+        let lib = markup::parse(StdOutErrorReporter, BufReader::new(mk.as_bytes()));
+        let view = lib.views.values().next().unwrap();
+        let root = markup::instantiate_repeats(view, &lib.templates, lengths);
+        let state_buffer = StateBuffer::new(&root, &stylesheet);
+
+        let text_buffer = TextBuffer::new(&root);
+
+        let mut buffer = LayoutBuffer::new(&root);
+        buffer.update_from_state(&state_buffer);
+        buffer.compute_layout(800.0, 600.0);
+        (state_buffer, text_buffer, buffer)
+    }
+
+    #[test]
+    fn absolute_boxes_should_be_placed_with_their_offsets() {
+        let buffer = layout(
+            ".screen { width: 100vw; height: 100vh; }\
+             .hud { layout: absolute; right: 10px; bottom: 20px; width: 100px; height: 50px; }\
+             .box { width: 200px; height: 30px; }",
+            "<view>\
+                <group class=\"screen\">\
+                    <button class=\"hud\"></button>\
+                    <button class=\"box\"></button>\
+                </group>\
+            </view>");
+
+        let hud = buffer[2].dim().content;
+        let flowing = buffer[3].dim().content;
+        assert_eq!((hud.x, hud.y), (690.0, 530.0));
+        // The absolute box doesn't take space in the line.
+        assert_eq!((flowing.x, flowing.y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn height_expand_should_take_the_height_left() {
+        let buffer = layout(
+            ".screen { width: 100vw; height: 100vh; }\
+             .header { width: 800px; height: 40px; }\
+             .body { width: 800px; height: expand; }",
+            "<view>\
+                <group class=\"screen\">\
+                    <group class=\"header\"></group>\
+                    <group class=\"body\"></group>\
+                </group>\
+            </view>");
+
+        let body = buffer[3].dim().content;
+        assert_eq!((body.y, body.height), (40.0, 560.0));
+    }
+
+    #[test]
+    fn margin_top_expand_should_pin_a_node_to_the_bottom() {
+        let buffer = layout(
+            ".screen { width: 100vw; height: 100vh; }\
+             .header { width: 800px; height: 40px; }\
+             .footer { width: 800px; height: 50px; margin-top: expand; }",
+            "<view>\
+                <group class=\"screen\">\
+                    <group class=\"header\"></group>\
+                    <group class=\"footer\"></group>\
+                </group>\
+            </view>");
+
+        assert_eq!(buffer[3].dim().border_box().y, 550.0);
+    }
+
+    #[test]
+    fn sizes_should_respect_min_and_max() {
+        let buffer = layout(
+            ".dialog { width: 100px; max-height: 80vh; }\
+             .item { width: 100px; height: 300px; }\
+             .group { width: 100px; }\
+             .btn { width: auto; min-width: 200px; }",
+            "<view>\
+                <group class=\"dialog\">\
+                    <group class=\"item\"></group>\
+                    <group class=\"item\"></group>\
+                    <group class=\"item\"></group>\
+                </group>\
+                <group class=\"group\">\
+                    <button class=\"btn\"></button>\
+                </group>\
+            </view>");
+
+        assert_eq!(buffer[1].dim().content.height, 480.0);
+        assert_eq!(buffer[6].dim().content.width, 200.0);
+    }
+
+    #[test]
+    fn column_should_center_and_distribute_its_children() {
+        let buffer = layout(
+            ".menu { layout: column; width: 100vw; height: 100vh;\
+                     align-items: center; justify-content: space-evenly; }\
+             .btn { width: 200px; height: 100px; }",
+            "<view>\
+                <group class=\"menu\">\
+                    <button class=\"btn\"></button>\
+                    <button class=\"btn\"></button>\
+                    <button class=\"btn\"></button>\
+                </group>\
+            </view>");
+
+        let positions: Vec<_> = (2..5)
+            .map(|i| (buffer[i].dim().content.x, buffer[i].dim().content.y))
+            .collect();
+        assert_eq!(positions, vec![(300.0, 75.0), (300.0, 250.0), (300.0, 425.0)]);
+    }
+
+    #[test]
+    fn row_should_grow_and_stretch_its_children() {
+        let buffer = layout(
+            ".bar { layout: row; width: 800px; height: 50px; gap: 20px; }\
+             .a { width: 100px; flex-grow: 1; }\
+             .b { width: 100px; flex-grow: 3; }",
+            "<view>\
+                <group class=\"bar\">\
+                    <group class=\"a\"></group>\
+                    <group class=\"b\"></group>\
+                </group>\
+            </view>");
+
+        let a = buffer[2].dim().content;
+        let b = buffer[3].dim().content;
+        assert_eq!((a.width, a.height), (245.0, 50.0));
+        assert_eq!((b.x, b.width), (265.0, 535.0));
+    }
+
+    #[test]
+    fn text_should_wrap_on_the_final_width_of_its_parent() {
+        let (_, _, mut buffer) = buffers(
+            ".bar { layout: row; width: 300px; height: 50px; }\
+             .btn { width: 200px; }\
+             .label { width: 10px; flex-grow: 1; }",
+            "<view>\
+                <group class=\"bar\">\
+                    <button class=\"btn\"></button>\
+                    <group class=\"label\">aaa bbb ccc</group>\
+                </group>\
+            </view>",
+            &[]);
+
+        // Every char is 10px wide.
+        let metrics = TextMetrics::new("aaa bbb ccc", WhiteSpace::Normal, 20.0,
+            |s| s.chars().count() as f32 * 10.0);
+        buffer.tree[4].set_text(metrics, false);
+        buffer.compute_layout(800.0, 600.0);
+
+        // Wrapped on the 100px the label grew to.
+        let text = buffer[4].dim().content;
+        assert_eq!((text.width, text.height), (70.0, 40.0));
+    }
+
+    #[test]
+    fn texts_and_bindings_should_keep_the_spaces_between_them() {
+        let (_, _, mut buffer) = buffers(
+            ".bar { width: 300px; height: 50px; }",
+            "<view>\
+                <group class=\"bar\">Welcome {{player.name}} to OIL!</group>\
+            </view>",
+            &[]);
+
+        // Every char is 10px wide.
+        for &(i, text, before, after) in [
+            (2, "Welcome ", false, true),
+            (3, "Bob", true, true),
+            (4, " to OIL!", true, false),
+        ].iter() {
+            let mut metrics = TextMetrics::new(text, WhiteSpace::Normal, 20.0,
+                |s| s.chars().count() as f32 * 10.0);
+            metrics.keep_edge_spaces(before, after);
+            buffer.tree[i].set_text(metrics, false);
+        }
+        buffer.compute_layout(800.0, 600.0);
+
+        let boxes: Vec<_> = (2..5)
+            .map(|i| (buffer[i].dim().content.x, buffer[i].dim().content.width))
+            .collect();
+        assert_eq!(boxes, vec![(0.0, 80.0), (80.0, 30.0), (110.0, 80.0)]);
+    }
+
+    #[test]
+    fn absolute_boxes_should_be_painted_last_by_z_index() {
+        let buffer = layout(
+            ".top { layout: absolute; z-index: 2; }\
+             .bottom { layout: absolute; z-index: 1; }",
+            "<view>\
+                <button class=\"top\"></button>\
+                <button class=\"bottom\"></button>\
+                <button></button>\
+            </view>");
+
+        assert_eq!(buffer.paint_order().to_vec(), vec![0, 3, 2, 1]);
+    }
+
+    #[test]
+    fn grid_should_fill_its_cells_with_the_repeat_instances() {
+        let buffer = layout_with_repeats(
+            ".inventory { layout: grid; width: 420px; gap: 10px;\
+                          grid-columns: 100px 1fr 2fr; grid-rows: 50px; }\
+             .item { height: 20px; }",
+            "<view>\
+                <group class=\"inventory\">\
+                    <repeat iter=\"{{items}}\" template-name=\"item\"/>\
+                </group>\
+            </view>\
+            <template name=\"item\">\
+                <button class=\"item\"></button>\
+            </template>",
+            &[4]);
+
+        // view, grid, repeat, then each instance followed by its button.
+        let cells: Vec<_> = (0..4)
+            .map(|i| buffer[3 + 2 * i].dim().content)
+            .map(|c| (c.x, c.y, c.width, c.height))
+            .collect();
+        assert_eq!(cells, vec![
+            (0.0, 0.0, 100.0, 50.0),
+            (110.0, 0.0, 100.0, 50.0),
+            (220.0, 0.0, 200.0, 50.0),
+            (0.0, 60.0, 100.0, 20.0),
+        ]);
+        assert_eq!(buffer[1].dim().content.height, 80.0);
+    }
+
+    #[test]
+    fn scroll_should_clip_and_follow_the_focused_node() {
+        let mut buffer = layout(
+            ".list { overflow: scroll; width: 200px; height: 100px; }\
+             .item { width: 200px; height: 40px; }",
+            "<view>\
+                <group class=\"list\">\
+                    <button class=\"item\"></button>\
+                    <button class=\"item\"></button>\
+                    <button class=\"item\"></button>\
+                    <button class=\"item\"></button>\
+                    <button class=\"item\"></button>\
+                </group>\
+            </view>");
+
+        let clips: Vec<_> = buffer.clips().iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(clips, vec![(2, 7)]);
+        // The items are not squeezed in the height of the list.
+        assert_eq!(buffer[6].dim().content.y, 160.0);
+
+        buffer.scroll_into_view(6);
+        assert_eq!(buffer[6].dim().content.y, 60.0);
+
+        // The offset is kept by the next layout.
+        buffer.compute_layout(800.0, 600.0);
+        assert_eq!(buffer[6].dim().content.y, 60.0);
+
+        assert!(buffer.scroll_by(1, 0.0, -30.0));
+        assert_eq!(buffer[2].dim().content.y, -70.0);
+        // It stops at the top of the content.
+        assert!(buffer.scroll_by(1, 0.0, -100.0));
+        assert_eq!(buffer[2].dim().content.y, 0.0);
+    }
+
+    #[test]
+    fn clips_inside_a_scroll_container_should_follow_the_scroll() {
+        let mut buffer = layout(
+            ".list { overflow: scroll; width: 200px; height: 100px; }\
+             .item { width: 200px; height: 40px; }\
+             .box { overflow: hidden; width: 200px; height: 40px; }",
+            "<view>\
+                <group class=\"list\">\
+                    <button class=\"item\"></button>\
+                    <button class=\"item\"></button>\
+                    <button class=\"item\"></button>\
+                    <group class=\"box\">\
+                        <button class=\"item\"></button>\
+                    </group>\
+                </group>\
+            </view>");
+
+        let clips: Vec<_> = buffer.clips().iter().map(|c| (c.start, c.end, c.rect.y)).collect();
+        assert_eq!(clips, vec![(2, 7, 0.0), (6, 7, 120.0)]);
+
+        assert!(buffer.scroll_by(1, 0.0, 30.0));
+        let clips: Vec<_> = buffer.clips().iter().map(|c| (c.start, c.end, c.rect.y)).collect();
+        assert_eq!(clips, vec![(2, 7, 0.0), (6, 7, 90.0)]);
+
+        buffer.scroll_into_view(2);
+        assert_eq!(buffer.clips()[1].rect.y, 120.0);
+    }
+
+    #[test]
+    fn virtual_repeat_should_place_its_instances_on_their_item_row() {
+        use markup::RepeatWindow;
+
+        let mut buffer = layout_with_repeats(
+            ".list { overflow: scroll; width: 200px; height: 100px; }\
+             .row { width: 200px; height: 20px; }",
+            "<view>\
+                <group class=\"list\">\
+                    <repeat iter=\"{{auctions}}\" template-name=\"auction\" virtual=\"true\"/>\
+                </group>\
+            </view>\
+            <template name=\"auction\">\
+                <button class=\"row\"></button>\
+            </template>",
+            &[4]);
+
+        // Four instances for a thousand items.
+        let current = [RepeatWindow { first: 2, slots: 4 }];
+        buffer.set_repeat_windows(&[1000], &current);
+        buffer.compute_layout(800.0, 600.0);
+
+        let rows: Vec<_> = (0..4).map(|slot| buffer[3 + 2 * slot].dim().content.y).collect();
+        assert_eq!(rows, vec![80.0, 100.0, 40.0, 60.0]);
+        assert_eq!(buffer[2].dim().content.height, 20000.0);
+
+        // Five visible rows and two more on each side.
+        assert_eq!(buffer.repeat_windows(&current, 2), vec![RepeatWindow { first: 0, slots: 9 }]);
+        buffer.scroll_by(1, 0.0, 500.0);
+        assert_eq!(buffer.repeat_windows(&current, 2), vec![RepeatWindow { first: 23, slots: 9 }]);
+    }
+
+    #[test]
+    fn dirty_layout_should_stop_at_the_nodes_keeping_their_size() {
+        let (mut state_buffer, text_buffer, mut buffer) = buffers(
+            ".screen { width: 100vw; height: 100vh; }\
+             .panel { width: 400px; height: 200px; }\
+             .box { width: 400px; height: 30px; }\
+             .box:focus { height: 60px; }\
+             .hud { layout: absolute; right: 10px; top: 10px; width: 100px; height: 20px; }\
+             .hud:focus { width: 150px; }",
+            "<view>\
+                <group class=\"screen\">\
+                    <group class=\"panel\">\
+                        <button class=\"box\"></button>\
+                        <button class=\"box\"></button>\
+                    </group>\
+                    <button class=\"hud\"></button>\
+                </group>\
+            </view>",
+            &[]);
+
+        // The box grows, the panel keeps its size.
+        state_buffer.get_mut(3).unwrap().set_current_state(SelectorState::Focus);
+        buffer.mark_dirty(3);
+        let laid_out = buffer.compute_dirty_layout(&state_buffer, &text_buffer, 800.0, 600.0);
+        assert_eq!(laid_out, vec![2..5]);
+        assert_eq!(buffer[3].dim().content.height, 60.0);
+        assert_eq!(buffer[4].dim().content.y, 60.0);
+
+        // An absolute box is laid out on its own.
+        state_buffer.get_mut(5).unwrap().set_current_state(SelectorState::Focus);
+        buffer.mark_dirty(5);
+        let laid_out = buffer.compute_dirty_layout(&state_buffer, &text_buffer, 800.0, 600.0);
+        assert_eq!(laid_out, vec![5..6]);
+        let hud = buffer[5].dim().content;
+        assert_eq!((hud.x, hud.y, hud.width), (640.0, 10.0, 150.0));
+        assert!(buffer.dirty_subtrees().is_empty());
+    }
 }
@@ -1,11 +1,15 @@
 use super::dim::{self, DimFlags};
-use super::{Dimensions, EdgeSizes, Rect};
+use super::{Dimensions, Length, EdgeLengths, Units, Flex, FlexDirection, AlignItems, JustifyContent};
+use super::{Grid, Overflow, Scroll, VirtualRepeat};
 use oil_shared::properties::PropertyName;
+use oil_shared::style::KwValue;
 use state::StateData;
+use text::TextMetrics;
 
 /// Reexport
 pub use self::buffer::LayoutBuffer;
 pub use self::buffer::LayoutNode;
+pub use self::buffer::Clip;
 
 mod buffer;
 
@@ -17,14 +21,95 @@ pub struct LayoutBox {
     dim: Dimensions,
     // Stores auto/fixed behaviors
     flags: DimFlags,
+    // Lengths from the style, resolved into `dim`
+    // during the layout.
+    spec: BoxLengths,
+    // Offsets of an absolute box from the content
+    // box of its parent, resolved from `spec`.
+    offsets: Offsets,
+    // Min and max sizes of the content box, resolved from `spec`.
+    limits: Limits,
+    // Painting order among the absolute siblings.
+    z_index: f32,
+    // Set when the children are stacked (`layout: row | column`).
+    flex: Option<Flex>,
+    // Grow and shrink factors as a child of a stack.
+    grow: f32,
+    shrink: f32,
+    // Sizes imposed by the parent stack, they
+    // take precedence over the style ones.
+    flex_width: Option<f32>,
+    flex_height: Option<f32>,
+    // Set when the children are placed in cells (`layout: grid`).
+    grid: Option<Grid>,
+    // Widths of the grid columns, resolved with the width.
+    grid_columns: Vec<f32>,
+    // A repeat lends its instances to the parent grid.
+    is_repeat: bool,
+    // Set on the repeats with only their visible items instantiated.
+    virtual_repeat: Option<VirtualRepeat>,
+    // Clipping of the children.
+    overflow: Overflow,
+    // Kept from one layout to the next one.
+    scroll: Scroll,
+    // Only for text nodes
+    text: Option<TextBox>,
+    // Set when the node must be laid out again.
+    dirty: bool,
+    // Space given by the parent in the last layout,
+    // to lay this node out again on its own.
+    given: Given,
 }
 
-// TODO:
-pub struct LayoutBoxRepeat {
-    dim: Dimensions,
-    flags: DimFlags,
-    // repeater: TemplateSomething,
-    // Vec<DataPerTemplateRequired>
+#[derive(Default)]
+struct BoxLengths {
+    width: Option<Length>,
+    height: Option<Length>,
+    padding: EdgeLengths,
+    border: EdgeLengths,
+    margin: EdgeLengths,
+    left: Option<Length>,
+    right: Option<Length>,
+    top: Option<Length>,
+    bottom: Option<Length>,
+    min_width: Option<Length>,
+    max_width: Option<Length>,
+    min_height: Option<Length>,
+    max_height: Option<Length>,
+}
+
+#[derive(Copy, Clone, Default)]
+struct Limits {
+    min_width: Option<f32>,
+    max_width: Option<f32>,
+    min_height: Option<f32>,
+    max_height: Option<f32>,
+}
+
+#[derive(Copy, Clone, Default)]
+struct Offsets {
+    left: Option<f32>,
+    right: Option<f32>,
+    top: Option<f32>,
+    bottom: Option<f32>,
+}
+
+// Arguments of the last calls to the layout functions.
+#[derive(Copy, Clone, Default)]
+struct Given {
+    space_for_self: f32,
+    parent_width: f32,
+    auto_space: f32,
+    max_height: f32,
+    parent_height: f32,
+}
+
+// A text node is broken into lines during the layout.
+struct TextBox {
+    metrics: TextMetrics,
+    // Take all the width available instead of the one
+    // of the longest line. (for `text-align` != left)
+    fill_width: bool,
 }
 
 // ======================================== //
@@ -85,6 +170,15 @@ impl LayoutBox {
             }
         }
 
+        // `margin: expand` is horizontal only.
+        if state.has_property_expand(PropertyName::MARGIN_TOP) {
+            flags = flags | dim::MARGIN_TOP_EXPAND;
+        }
+
+        if state.has_property_expand(PropertyName::MARGIN_BOTTOM) {
+            flags = flags | dim::MARGIN_BOT_EXPAND;
+        }
+
         if state.has_property_auto(PropertyName::WIDTH) {
             flags = flags | dim::WIDTH_AUTO;
         }
@@ -93,64 +187,199 @@ impl LayoutBox {
             flags = flags | dim::WIDTH_EXPAND;
         }
 
+        if state.has_property_auto(PropertyName::HEIGHT) {
+            flags = flags | dim::HEIGHT_AUTO;
+        }
+
+        if state.has_property_expand(PropertyName::HEIGHT) {
+            flags = flags | dim::HEIGHT_EXPAND;
+        }
+
         // Sizes
-        let padding_left = state.size_of_prop(PropertyName::PADDING_LEFT);
-        let padding_right = state.size_of_prop(PropertyName::PADDING_RIGHT);
-        let padding_top = state.size_of_prop(PropertyName::PADDING_TOP);
-        let padding_bottom = state.size_of_prop(PropertyName::PADDING_BOTTOM);
-
-        let margin_left = state.size_of_prop(PropertyName::MARGIN_LEFT);
-        let margin_right = state.size_of_prop(PropertyName::MARGIN_RIGHT);
-        let margin_top = state.size_of_prop(PropertyName::MARGIN_TOP);
-        let margin_bottom = state.size_of_prop(PropertyName::MARGIN_BOTTOM);
-
-        let border_left = state.size_of_prop(PropertyName::BORDER_LEFT);
-        let border_right = state.size_of_prop(PropertyName::BORDER_RIGHT);
-        let border_top = state.size_of_prop(PropertyName::BORDER_TOP);
-        let border_bottom = state.size_of_prop(PropertyName::BORDER_BOTTOM);
-
-        let width = match state.size_prop_as_opt(PropertyName::WIDTH) {
-            Some(w) => {
-                flags = flags | dim::WIDTH_FIXED;
-                w
-            }
-            None => 0f32
-        };
+        if state.length_prop_as_opt(PropertyName::WIDTH).is_some() {
+            flags = flags | dim::WIDTH_FIXED;
+        }
 
-        let height = match state.size_prop_as_opt(PropertyName::HEIGHT) {
-            Some(h) => {
-                flags = flags | dim::HEIGHT_FIXED;
-                h
+        if state.length_prop_as_opt(PropertyName::HEIGHT).is_some() {
+            flags = flags | dim::HEIGHT_FIXED;
+        }
+
+        // Absolute positioning
+        let left = state.length_prop_as_opt(PropertyName::LEFT);
+        let right = state.length_prop_as_opt(PropertyName::RIGHT);
+        let top = state.length_prop_as_opt(PropertyName::TOP);
+        let bottom = state.length_prop_as_opt(PropertyName::BOTTOM);
+
+        if state.has_property_eq_kw(PropertyName::LAYOUT_MODE, KwValue::Absolute) {
+            flags = flags | dim::ABSOLUTE_POSITIONING;
+
+            // Stretched between the two offsets.
+            if left.is_some() && right.is_some()
+                && !flags.has_width_fixed() && !flags.has_width_auto()
+            {
+                flags = flags | dim::WIDTH_EXPAND;
             }
-            None => 0f32
-        };
+        }
 
-        // TODO: Missing bit for left / right / top / bottom
-        //       We also need at some point the relative information
-        self.dim.content = Rect {
-            x: 0f32,
-            y: 0f32,
-            width: width,
-            height: height,
-        };
-        self.dim.padding = EdgeSizes {
-            left: padding_left,
-            right: padding_right,
-            top: padding_top,
-            bottom: padding_bottom,
-        };
-        self.dim.border = EdgeSizes {
-            left: border_left,
-            right: border_right,
-            top: border_top,
-            bottom: border_bottom
+        self.z_index = state.size_prop_as_opt(PropertyName::Z_INDEX).unwrap_or(0f32);
+        self.flex = state.get_flex();
+        self.grid = state.get_grid();
+
+        // The rows of a virtual repeat take its width.
+        if self.virtual_repeat.is_some() {
+            self.flex = Some(Flex {
+                direction: FlexDirection::Column,
+                align_items: AlignItems::Stretch,
+                justify_content: JustifyContent::Start,
+                gap: Length::default(),
+            });
+        }
+        self.overflow = if state.has_property_eq_kw(PropertyName::OVERFLOW, KwValue::Scroll) {
+            Overflow::Scroll
+        } else if state.has_property_eq_kw(PropertyName::OVERFLOW, KwValue::Hidden) {
+            Overflow::Hidden
+        } else {
+            Overflow::Visible
         };
-        self.dim.margin = EdgeSizes {
-            left: margin_left,
-            right: margin_right,
-            top: margin_top,
-            bottom: margin_bottom
+        self.grow = state.size_prop_as_opt(PropertyName::FLEX_GROW).unwrap_or(0f32);
+        self.shrink = state.size_prop_as_opt(PropertyName::FLEX_SHRINK).unwrap_or(1f32);
+        self.spec = BoxLengths {
+            width: state.length_prop_as_opt(PropertyName::WIDTH),
+            height: state.length_prop_as_opt(PropertyName::HEIGHT),
+            padding: EdgeLengths {
+                left: state.length_of_prop(PropertyName::PADDING_LEFT),
+                right: state.length_of_prop(PropertyName::PADDING_RIGHT),
+                top: state.length_of_prop(PropertyName::PADDING_TOP),
+                bottom: state.length_of_prop(PropertyName::PADDING_BOTTOM),
+            },
+            border: EdgeLengths {
+                left: state.length_of_prop(PropertyName::BORDER_LEFT),
+                right: state.length_of_prop(PropertyName::BORDER_RIGHT),
+                top: state.length_of_prop(PropertyName::BORDER_TOP),
+                bottom: state.length_of_prop(PropertyName::BORDER_BOTTOM),
+            },
+            margin: EdgeLengths {
+                left: state.length_of_prop(PropertyName::MARGIN_LEFT),
+                right: state.length_of_prop(PropertyName::MARGIN_RIGHT),
+                top: state.length_of_prop(PropertyName::MARGIN_TOP),
+                bottom: state.length_of_prop(PropertyName::MARGIN_BOTTOM),
+            },
+            left: left,
+            right: right,
+            top: top,
+            bottom: bottom,
+            min_width: state.length_prop_as_opt(PropertyName::MIN_WIDTH),
+            max_width: state.length_prop_as_opt(PropertyName::MAX_WIDTH),
+            min_height: state.length_prop_as_opt(PropertyName::MIN_HEIGHT),
+            max_height: state.length_prop_as_opt(PropertyName::MAX_HEIGHT),
         };
+        self.dim = Dimensions::default();
         self.flags = flags;
     }
+
+    /// Convert the horizontal lengths into pixels.
+    /// Percentages are relative to `parent_width`.
+    pub fn resolve_horizontal(&mut self, units: &Units, parent_width: f32) {
+        let spec = &self.spec;
+        self.limits.min_width = spec.min_width.map(|w| w.resolve(units, parent_width));
+        self.limits.max_width = spec.max_width.map(|w| w.resolve(units, parent_width));
+        if let Some(width) = spec.width {
+            if !self.is_text() {
+                self.dim.content.width = self.clamp_width(width.resolve(units, parent_width));
+            }
+        }
+        self.dim.padding.left = spec.padding.left.resolve(units, parent_width);
+        self.dim.padding.right = spec.padding.right.resolve(units, parent_width);
+        self.dim.border.left = spec.border.left.resolve(units, parent_width);
+        self.dim.border.right = spec.border.right.resolve(units, parent_width);
+        self.dim.margin.left = spec.margin.left.resolve(units, parent_width);
+        self.dim.margin.right = spec.margin.right.resolve(units, parent_width);
+        self.offsets.left = spec.left.map(|l| l.resolve(units, parent_width));
+        self.offsets.right = spec.right.map(|r| r.resolve(units, parent_width));
+    }
+
+    /// Convert the vertical lengths into pixels.
+    /// Percentages are relative to `parent_height`.
+    pub fn resolve_vertical(&mut self, units: &Units, parent_height: f32) {
+        let spec = &self.spec;
+        self.limits.min_height = spec.min_height.map(|h| h.resolve(units, parent_height));
+        self.limits.max_height = spec.max_height.map(|h| h.resolve(units, parent_height));
+        if let Some(height) = spec.height {
+            if !self.is_text() {
+                self.dim.content.height = self.clamp_height(height.resolve(units, parent_height));
+            }
+        } else if !self.is_text() {
+            self.dim.content.height = 0f32;
+        }
+        self.dim.padding.top = spec.padding.top.resolve(units, parent_height);
+        self.dim.padding.bottom = spec.padding.bottom.resolve(units, parent_height);
+        self.dim.border.top = spec.border.top.resolve(units, parent_height);
+        self.dim.border.bottom = spec.border.bottom.resolve(units, parent_height);
+        self.dim.margin.top = spec.margin.top.resolve(units, parent_height);
+        self.dim.margin.bottom = spec.margin.bottom.resolve(units, parent_height);
+        self.offsets.top = spec.top.map(|t| t.resolve(units, parent_height));
+        self.offsets.bottom = spec.bottom.map(|b| b.resolve(units, parent_height));
+    }
+
+    /// Apply `min-width` and `max-width` to a content width.
+    /// The min wins over the max, as in CSS.
+    pub fn clamp_width(&self, width: f32) -> f32 {
+        let width = self.limits.max_width.map_or(width, |max| width.min(max));
+        self.limits.min_width.map_or(width, |min| width.max(min))
+    }
+
+    /// Apply `min-height` and `max-height` to a content height.
+    pub fn clamp_height(&self, height: f32) -> f32 {
+        let height = self.limits.max_height.map_or(height, |max| height.min(max));
+        self.limits.min_height.map_or(height, |min| height.max(min))
+    }
+
+    /// Turn this box into a text box. Its size will be
+    /// the one of the text broken into lines.
+    pub fn set_text(&mut self, metrics: TextMetrics, fill_width: bool) {
+        self.text = Some(TextBox {
+            metrics: metrics,
+            fill_width: fill_width,
+        });
+    }
+
+    /// Returns true if this box is out of the line flow
+    /// (`layout: absolute`).
+    #[inline]
+    pub fn is_absolute(&self) -> bool {
+        self.flags.is_absolute()
+    }
+
+    /// Returns true if the children are clipped
+    /// to the content box (`overflow: hidden | scroll`).
+    #[inline]
+    pub fn clips(&self) -> bool {
+        self.overflow != Overflow::Visible
+    }
+
+    #[inline]
+    pub fn is_text(&self) -> bool {
+        self.text.is_some()
+    }
+
+    /// Break the text into lines no wider than `max_width`
+    /// and give a fixed size to the box accordingly.
+    /// Returns the width used.
+    pub fn layout_text(&mut self, max_width: f32) -> f32 {
+        let (width, height) = match self.text {
+            Some(ref text) => {
+                let (width, height) = text.metrics.size(max_width);
+                if text.fill_width {
+                    (width.max(max_width), height)
+                } else {
+                    (width, height)
+                }
+            }
+            None => return 0f32,
+        };
+        self.dim.content.width = width;
+        self.dim.content.height = height;
+        self.flags = self.flags | dim::WIDTH_FIXED | dim::HEIGHT_FIXED;
+        width
+    }
 }
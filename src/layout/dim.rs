@@ -14,6 +14,47 @@ pub struct Dimensions {
     pub margin: EdgeSizes,
 }
 
+impl Dimensions {
+
+    /// The area covered by the content, the padding and the border.
+    /// Backgrounds are drawn over this area.
+    pub fn border_box(&self) -> Rect {
+        Rect {
+            x: self.content.x + self.margin.left,
+            y: self.content.y + self.margin.top,
+            width: self.content.width
+                + self.padding.left + self.padding.right
+                + self.border.left + self.border.right,
+            height: self.content.height
+                + self.padding.top + self.padding.bottom
+                + self.border.top + self.border.bottom,
+        }
+    }
+
+    /// The area covered by the content only.
+    /// Children are laid out in this area.
+    pub fn content_box(&self) -> Rect {
+        Rect {
+            x: self.content.x + self.margin.left + self.border.left + self.padding.left,
+            y: self.content.y + self.margin.top + self.border.top + self.padding.top,
+            width: self.content.width,
+            height: self.content.height,
+        }
+    }
+
+    /// The area covered by the box, margins included.
+    /// This is the space it takes in its parent.
+    pub fn margin_box(&self) -> Rect {
+        let border_box = self.border_box();
+        Rect {
+            x: self.content.x,
+            y: self.content.y,
+            width: border_box.width + self.margin.left + self.margin.right,
+            height: border_box.height + self.margin.top + self.margin.bottom,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct EdgeSizes {
     pub left: f32,
@@ -27,6 +68,11 @@ bitflags! {
     flags DimFlags: u16 {
         // A text node is WIDTH_FIXED,
         // A node with a style fixed width is naturally WIDTH_FIXED
+        const MARGIN_BOT_EXPAND     = 0b0100_0000_0000_0000,
+        const MARGIN_TOP_EXPAND     = 0b0010_0000_0000_0000,
+        const HEIGHT_EXPAND         = 0b0001_0000_0000_0000,
+        const HEIGHT_AUTO           = 0b0000_1000_0000_0000,
+
         const ABSOLUTE_POSITIONING  = 0b0100_0000_0000,
         const HEIGHT_FIXED          = 0b0010_0000_0000,
         const WIDTH_FIXED           = 0b0001_0000_0000,
@@ -47,6 +93,8 @@ bitflags! {
                                     | MARGIN_BOT_AUTO.bits,
         const MARGIN_X_EXPAND       = MARGIN_LEFT_EXPAND.bits
                                     | MARGIN_RIGHT_EXPAND.bits,
+        const MARGIN_Y_EXPAND       = MARGIN_TOP_EXPAND.bits
+                                    | MARGIN_BOT_EXPAND.bits,
     }
 }
 
@@ -66,6 +114,11 @@ impl DimFlags {
         self.is_x_auto() || self.is_x_expand()
     }
 
+    #[inline]
+    pub fn is_absolute(&self) -> bool {
+        self.contains(ABSOLUTE_POSITIONING)
+    }
+
     #[inline]
     pub fn has_width_auto(&self) -> bool {
         self.contains(WIDTH_AUTO)
@@ -86,6 +139,16 @@ impl DimFlags {
         self.contains(HEIGHT_FIXED)
     }
 
+    #[inline]
+    pub fn has_height_auto(&self) -> bool {
+        self.contains(HEIGHT_AUTO)
+    }
+
+    #[inline]
+    pub fn has_height_expand(&self) -> bool {
+        self.contains(HEIGHT_EXPAND)
+    }
+
     #[inline]
     pub fn has_margin_top_or_bot_auto(&self) -> bool {
         self.intersects(MARGIN_Y_AUTO)
@@ -111,6 +174,16 @@ impl DimFlags {
         self.contains(MARGIN_RIGHT_AUTO)
     }
 
+    #[inline]
+    pub fn has_margin_top_expand(&self) -> bool {
+        self.contains(MARGIN_TOP_EXPAND)
+    }
+
+    #[inline]
+    pub fn has_margin_bottom_expand(&self) -> bool {
+        self.contains(MARGIN_BOT_EXPAND)
+    }
+
     #[inline]
     pub fn has_margin_top_auto(&self) -> bool {
         self.contains(MARGIN_TOP_AUTO)
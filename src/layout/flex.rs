@@ -0,0 +1,150 @@
+use super::Length;
+
+/// Main axis of a stack layout (`layout: row | column`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FlexDirection {
+    Row,
+    Column,
+}
+
+/// Placement of the children on the cross axis (`align-items`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlignItems {
+    Start,
+    Center,
+    End,
+    /// The children without a fixed size take the whole cross axis.
+    Stretch,
+}
+
+/// Distribution of the free space on the main axis (`justify-content`).
+/// Same meaning as in CSS.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum JustifyContent {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+impl Default for AlignItems {
+    fn default() -> AlignItems {
+        AlignItems::Stretch
+    }
+}
+
+impl Default for JustifyContent {
+    fn default() -> JustifyContent {
+        JustifyContent::Start
+    }
+}
+
+/// Properties of a node whose children are stacked.
+#[derive(Copy, Clone, Debug)]
+pub struct Flex {
+    pub direction: FlexDirection,
+    pub align_items: AlignItems,
+    pub justify_content: JustifyContent,
+    /// Space between two children on the main axis.
+    pub gap: Length,
+}
+
+/// Returns the sizes once the free space is given to the items
+/// according to their grow factor, or taken from them according
+/// to their shrink factor weighted by their size.
+/// `factors` contains the (grow, shrink) factors of each item.
+pub fn grow_or_shrink(sizes: &[f32], factors: &[(f32, f32)], free: f32) -> Vec<f32> {
+    let total_grow = factors.iter().fold(0f32, |t, &(g, _)| t + g);
+    let total_shrink = sizes.iter().zip(factors.iter())
+        .fold(0f32, |t, (&size, &(_, s))| t + s * size);
+
+    sizes.iter().zip(factors.iter())
+        .map(|(&size, &(grow, shrink))| {
+            if free > 0f32 && total_grow > 0f32 {
+                size + free * grow / total_grow
+            } else if free < 0f32 && total_shrink > 0f32 {
+                (size + free * shrink * size / total_shrink).max(0f32)
+            } else {
+                size
+            }
+        })
+        .collect()
+}
+
+/// Returns the offset of each item from the start of the main axis.
+pub fn justify_offsets(
+    justify: JustifyContent,
+    sizes: &[f32],
+    gap: f32,
+    available: f32) -> Vec<f32>
+{
+    let n = sizes.len() as f32;
+    let used = sizes.iter().fold(0f32, |t, &s| t + s) + gap * (n - 1f32).max(0f32);
+    let free = (available - used).max(0f32);
+
+    let (start, spacing) = match justify {
+        JustifyContent::Start => (0f32, gap),
+        JustifyContent::Center => (free / 2f32, gap),
+        JustifyContent::End => (free, gap),
+        JustifyContent::SpaceBetween if n > 1f32 => (0f32, gap + free / (n - 1f32)),
+        JustifyContent::SpaceBetween => (0f32, gap),
+        JustifyContent::SpaceAround => (free / n / 2f32, gap + free / n),
+        JustifyContent::SpaceEvenly => (free / (n + 1f32), gap + free / (n + 1f32)),
+    };
+
+    let mut offset = start;
+    sizes.iter()
+        .map(|&size| {
+            let current = offset;
+            offset += size + spacing;
+            current
+        })
+        .collect()
+}
+
+/// Returns the offset of an item on the cross axis.
+pub fn align_offset(align: AlignItems, size: f32, available: f32) -> f32 {
+    match align {
+        AlignItems::Start | AlignItems::Stretch => 0f32,
+        AlignItems::Center => (available - size) / 2f32,
+        AlignItems::End => available - size,
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use super::{JustifyContent, grow_or_shrink, justify_offsets};
+
+    #[test]
+    fn free_space_should_follow_the_factors() {
+        let sizes = [100.0, 100.0, 200.0];
+
+        assert_eq!(
+            grow_or_shrink(&sizes, &[(1.0, 1.0), (3.0, 1.0), (0.0, 1.0)], 100.0),
+            vec![125.0, 175.0, 200.0]);
+        // Shrinking is weighted by the size.
+        assert_eq!(
+            grow_or_shrink(&sizes, &[(0.0, 1.0), (0.0, 0.0), (0.0, 1.0)], -60.0),
+            vec![80.0, 100.0, 160.0]);
+    }
+
+    #[test]
+    fn justify_should_distribute_the_free_space() {
+        let sizes = [20.0, 20.0, 20.0];
+        let offsets = |j| justify_offsets(j, &sizes, 10.0, 200.0);
+
+        assert_eq!(offsets(JustifyContent::Start), vec![0.0, 30.0, 60.0]);
+        assert_eq!(offsets(JustifyContent::Center), vec![60.0, 90.0, 120.0]);
+        assert_eq!(offsets(JustifyContent::End), vec![120.0, 150.0, 180.0]);
+        assert_eq!(offsets(JustifyContent::SpaceBetween), vec![0.0, 90.0, 180.0]);
+        assert_eq!(offsets(JustifyContent::SpaceAround), vec![20.0, 90.0, 160.0]);
+        assert_eq!(offsets(JustifyContent::SpaceEvenly), vec![30.0, 90.0, 150.0]);
+    }
+}
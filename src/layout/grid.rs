@@ -0,0 +1,128 @@
+use super::{Length, Units};
+
+/// Size of a row or a column of a grid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Track {
+    /// A length such as `100px` or `20%`.
+    Fixed(Length),
+    /// Share of the space left by the other tracks (`1fr`).
+    Fraction(f32),
+    /// Size of the largest cell.
+    Auto,
+}
+
+/// Properties of a node whose children are placed in
+/// the cells of a grid (`layout: grid`).
+#[derive(Clone, Debug)]
+pub struct Grid {
+    pub columns: Vec<Track>,
+    /// Rows after the last one defined are `auto`.
+    pub rows: Vec<Track>,
+    /// Space between two rows and two columns.
+    pub gap: Length,
+}
+
+impl Grid {
+
+    /// A grid without columns has a single `auto` one.
+    pub fn column_tracks(&self) -> Vec<Track> {
+        if self.columns.is_empty() {
+            vec![Track::Auto]
+        } else {
+            self.columns.clone()
+        }
+    }
+
+    pub fn row_tracks(&self, count: usize) -> Vec<Track> {
+        (0..count).map(|i| self.rows.get(i).map_or(Track::Auto, |&t| t)).collect()
+    }
+}
+
+/// Returns the size of each track. `contents` is the size of the
+/// largest cell of each track and `reference` the size percentages
+/// are relative to.
+///
+/// Fractions share what is left of `available` once the other
+/// tracks and the gaps are removed. Without `available`, the
+/// tracks are as large as their content.
+pub fn resolve_tracks(
+    tracks: &[Track],
+    contents: &[f32],
+    units: &Units,
+    reference: f32,
+    gap: f32,
+    available: Option<f32>) -> Vec<f32>
+{
+    let mut sizes: Vec<f32> = tracks.iter().zip(contents.iter())
+        .map(|(track, &content)| match *track {
+            Track::Fixed(length) => length.resolve(units, reference),
+            Track::Fraction(_) | Track::Auto => content,
+        })
+        .collect();
+
+    if let Some(available) = available {
+        let total_fraction = tracks.iter().fold(0f32, |t, track| match *track {
+            Track::Fraction(f) => t + f,
+            _ => t,
+        });
+
+        if total_fraction > 0f32 {
+            let gaps = gap * (tracks.len() as f32 - 1f32).max(0f32);
+            let free = tracks.iter().zip(sizes.iter())
+                .filter(|&(track, _)| match *track { Track::Fraction(_) => false, _ => true })
+                .fold(available - gaps, |t, (_, &size)| t - size)
+                .max(0f32);
+
+            for (size, track) in sizes.iter_mut().zip(tracks.iter()) {
+                if let Track::Fraction(f) = *track {
+                    *size = free * f / total_fraction;
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Returns the offset of each track from the start of the grid.
+pub fn track_offsets(sizes: &[f32], gap: f32) -> Vec<f32> {
+    let mut offset = 0f32;
+    sizes.iter()
+        .map(|&size| {
+            let current = offset;
+            offset += size + gap;
+            current
+        })
+        .collect()
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use layout::{Length, Units};
+    use super::{Track, resolve_tracks, track_offsets};
+
+    #[test]
+    fn fractions_should_share_the_space_left() {
+        let units = Units::new(800.0, 600.0, None);
+        let tracks = [
+            Track::Fixed(Length::px(100.0)),
+            Track::Auto,
+            Track::Fraction(1.0),
+            Track::Fraction(3.0),
+        ];
+        let contents = [0.0, 50.0, 20.0, 20.0];
+
+        // 500 - 100 - 50 - 3 * 10 = 320
+        let sizes = resolve_tracks(&tracks, &contents, &units, 500.0, 10.0, Some(500.0));
+        assert_eq!(sizes, vec![100.0, 50.0, 80.0, 240.0]);
+        assert_eq!(track_offsets(&sizes, 10.0), vec![0.0, 110.0, 170.0, 260.0]);
+
+        let natural = resolve_tracks(&tracks, &contents, &units, 500.0, 10.0, None);
+        assert_eq!(natural, vec![100.0, 50.0, 20.0, 20.0]);
+    }
+}
@@ -0,0 +1,108 @@
+use oil_shared::style::Unit;
+
+/// Size of `1rem` when the root of the view doesn't define `root-unit`.
+pub const DEFAULT_ROOT_UNIT: f32 = 16f32;
+
+/// A length as written in the style. It is
+/// resolved into pixels during the layout.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Length {
+    pub value: f32,
+    pub unit: Unit,
+}
+
+/// Lengths of the four edges of a box (margin, border or padding).
+#[derive(Copy, Clone, Default, Debug)]
+pub struct EdgeLengths {
+    pub left: Length,
+    pub right: Length,
+    pub top: Length,
+    pub bottom: Length,
+}
+
+/// Everything needed to convert a `Length` into pixels,
+/// except the size of the parent.
+#[derive(Copy, Clone, Debug)]
+pub struct Units {
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+    /// Size of `1rem` in pixels.
+    pub root_unit: f32,
+}
+
+impl Length {
+
+    #[inline]
+    pub fn px(value: f32) -> Length {
+        Length { value: value, unit: Unit::Px }
+    }
+
+    /// Returns the length in pixels. Percentages
+    /// are relative to `parent_size`.
+    pub fn resolve(&self, units: &Units, parent_size: f32) -> f32 {
+        match self.unit {
+            Unit::Px => self.value,
+            Unit::Percent => self.value / 100f32 * parent_size,
+            Unit::Vw => self.value / 100f32 * units.viewport_width,
+            Unit::Vh => self.value / 100f32 * units.viewport_height,
+            Unit::Rem => self.value * units.root_unit,
+            // Only grid tracks take fractions, the parser rejects
+            // them anywhere else and the tracks resolve them.
+            Unit::Fr => 0f32,
+        }
+    }
+}
+
+impl Default for Length {
+    fn default() -> Length {
+        Length::px(0f32)
+    }
+}
+
+impl Units {
+
+    /// `root_unit` is the `root-unit` property of the root node.
+    /// It is resolved against the viewport.
+    pub fn new(viewport_width: f32, viewport_height: f32, root_unit: Option<Length>) -> Units {
+        let mut units = Units {
+            viewport_width: viewport_width,
+            viewport_height: viewport_height,
+            root_unit: DEFAULT_ROOT_UNIT,
+        };
+        if let Some(root_unit) = root_unit {
+            units.root_unit = root_unit.resolve(&units, viewport_height);
+        }
+        units
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use oil_shared::style::Unit;
+    use super::{Length, Units};
+
+    #[test]
+    fn lengths_should_resolve_against_their_reference() {
+        let units = Units::new(1280.0, 720.0, None);
+        let length = |value: f32, unit: Unit| Length { value: value, unit: unit };
+
+        assert_eq!(length(12.0, Unit::Px).resolve(&units, 500.0), 12.0);
+        assert_eq!(length(50.0, Unit::Percent).resolve(&units, 500.0), 250.0);
+        assert_eq!(length(10.0, Unit::Vw).resolve(&units, 500.0), 128.0);
+        assert_eq!(length(10.0, Unit::Vh).resolve(&units, 500.0), 72.0);
+        assert_eq!(length(2.0, Unit::Rem).resolve(&units, 500.0), 32.0);
+    }
+
+    #[test]
+    fn root_unit_should_scale_with_the_viewport() {
+        let root_unit = Some(Length { value: 5.0, unit: Unit::Vh });
+
+        assert_eq!(Units::new(1280.0, 720.0, root_unit).root_unit, 36.0);
+        assert_eq!(Units::new(3840.0, 2160.0, root_unit).root_unit, 108.0);
+    }
+}
@@ -5,10 +5,19 @@ pub use self::dim::EdgeSizes;
 pub use self::boxes::LayoutBuffer;
 pub use self::boxes::LayoutBox;
 pub use self::boxes::LayoutNode;
+pub use self::boxes::Clip;
+pub use self::length::{Length, EdgeLengths, Units};
+pub use self::flex::{Flex, FlexDirection, AlignItems, JustifyContent};
+pub use self::grid::{Grid, Track};
+pub use self::scroll::{Overflow, Scroll, VirtualRepeat};
 
 mod rect;
 mod boxes;
 mod dim;
+mod length;
+mod flex;
+mod grid;
+mod scroll;
 
 #[cfg(test)]
 mod test {
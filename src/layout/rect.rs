@@ -2,7 +2,7 @@
 /// Rect is mainly used by
 /// layout and focus trees.
 ///
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -19,6 +19,23 @@ impl Rect {
     //     self.intersects_x(other) * self.intersects_y(other)
     // }
 
+    /// Returns the rectangle shared by `self` and `other`.
+    /// If they don't overlap, the rectangle returned has
+    /// a width or a height equal to zero.
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let xo = (self.x + self.width).min(other.x + other.width);
+        let yo = (self.y + self.height).min(other.y + other.height);
+
+        Rect {
+            x: x,
+            y: y,
+            width: (xo - x).max(0f32),
+            height: (yo - y).max(0f32),
+        }
+    }
+
     pub fn intersects_x(&self, other: &Rect) -> f32 {
         if self.x < other.x {
             if self.x + self.width > other.x {
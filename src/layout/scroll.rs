@@ -0,0 +1,101 @@
+/// What happens to the children that don't fit
+/// in the content box of their parent (`overflow`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Overflow {
+    Visible,
+    /// The children are clipped to the content box.
+    Hidden,
+    /// Same as `Hidden`, but the children can be scrolled.
+    Scroll,
+}
+
+impl Default for Overflow {
+    fn default() -> Overflow {
+        Overflow::Visible
+    }
+}
+
+/// Scroll offset of a node with `overflow: scroll`.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Scroll {
+    pub x: f32,
+    pub y: f32,
+    /// Size of the content hidden by the node,
+    /// the offset stays between 0 and these.
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Scroll {
+
+    /// Keep the offset within the content.
+    pub fn clamp(&mut self) {
+        self.x = self.x.min(self.max_x).max(0f32);
+        self.y = self.y.min(self.max_y).max(0f32);
+    }
+
+    /// Move the offset and return the move actually done.
+    pub fn scroll_by(&mut self, dx: f32, dy: f32) -> (f32, f32) {
+        let (x, y) = (self.x, self.y);
+        self.x += dx;
+        self.y += dy;
+        self.clamp();
+        (self.x - x, self.y - y)
+    }
+}
+
+/// Items of a virtual repeat (`<repeat virtual="true">`). Only a few
+/// instances exist, the repeat is laid out as if it had `total` rows
+/// as high as its instances.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct VirtualRepeat {
+    pub total: usize,
+    /// First item shown by the instances.
+    pub first: usize,
+    /// Height of a row, measured on the instances.
+    pub item_height: f32,
+}
+
+/// Returns how far the view must move on one axis to show
+/// the item. When the item is larger than the view, its
+/// start is shown.
+pub fn offset_to_show(start: f32, size: f32, view_start: f32, view_size: f32) -> f32 {
+    let end = start + size;
+    let view_end = view_start + view_size;
+
+    if start < view_start {
+        start - view_start
+    } else if end > view_end {
+        (end - view_end).min(start - view_start)
+    } else {
+        0f32
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use super::{Scroll, offset_to_show};
+
+    #[test]
+    fn scroll_should_stay_within_the_content() {
+        let mut scroll = Scroll { max_y: 100.0, .. Scroll::default() };
+
+        assert_eq!(scroll.scroll_by(0.0, 60.0), (0.0, 60.0));
+        assert_eq!(scroll.scroll_by(10.0, 60.0), (0.0, 40.0));
+        assert_eq!(scroll.scroll_by(0.0, -150.0), (0.0, -100.0));
+    }
+
+    #[test]
+    fn offset_should_bring_the_item_into_view() {
+        assert_eq!(offset_to_show(20.0, 10.0, 0.0, 100.0), 0.0);
+        assert_eq!(offset_to_show(120.0, 10.0, 0.0, 100.0), 30.0);
+        assert_eq!(offset_to_show(-30.0, 10.0, 0.0, 100.0), -30.0);
+        // Too large: its start is shown.
+        assert_eq!(offset_to_show(50.0, 200.0, 0.0, 100.0), 50.0);
+    }
+}
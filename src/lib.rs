@@ -7,17 +7,17 @@ extern crate bitflags;
 extern crate phf;
 extern crate num;
 
+#[cfg(feature = "glium")]
 #[macro_use]
 extern crate glium;
-extern crate image;
+#[cfg(feature = "glium")]
 extern crate cgmath;
+extern crate image;
+extern crate rusttype;
 extern crate oil_parsers;
 extern crate oil_shared;
 extern crate oil_databindings;
 
-#[cfg(test)]
-extern crate glutin;
-
 pub mod markup;
 pub mod style;
 pub mod deps;
@@ -29,6 +29,8 @@ pub mod data_bindings;
 pub use oil_parsers::ErrorReporter;
 pub use oil_parsers::StdOutErrorReporter;
 pub use oil_parsers::EmptyErrorReporter;
+pub use oil_parsers::VecErrorReporter;
+pub use oil_parsers::{Diagnostic, Severity, ErrorCode};
 pub use self::router::Router;
 pub use self::rendering::View;
 pub use self::data_bindings::DefaultContextManager;
@@ -40,23 +42,27 @@ mod router;
 mod util;
 mod focus;
 mod state;
+mod text;
 
 /// Trait used by oil to perform the high level rendering operations.
-/// Ideally, `oil` should not depend on a specific implementation.
 ///
-/// It turns out, today's this is not true at all.
+/// Views only produce a `DisplayList`, a backend is free to
+/// draw it the way it wants.
 pub trait RenderBackbend {
     type Frame;
+    /// Texture type expected from the `ResourceManager`.
+    type Texture;
 
     /// Prepare the frame for the current rendering step.
     fn prepare_frame(&mut self, vp: Viewport) -> Self::Frame;
 
-    /// Render an element on the current frame.
-    fn render_element<R : resource::ResourceManager>(
+    /// Execute the draw commands of the display list on the current frame.
+    fn render_display_list<R>(
         &self,
         resource_manager: &R,
         frame: &mut Self::Frame,
-        data: &rendering::RenderData);
+        list: &rendering::DisplayList)
+        where R: resource::ResourceManager<Texture=Self::Texture>;
 
     // Flush the frame. Typically, swap buffers.
     fn flush_frame(&self, frame: Self::Frame);
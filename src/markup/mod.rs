@@ -9,15 +9,21 @@ pub use oil_shared::markup::{
     LineInputData,
     ProgressBarData,
     TemplateData,
-    RepeatData
+    RepeatData,
+    RepeatBindingData
 };
 
 pub use oil_parsers::markup::Library;
 pub use oil_parsers::markup::MAIN_VIEW_NAME;
 pub use oil_parsers::markup::parse;
+pub use oil_parsers::markup::parse_file;
+pub use self::repeat::{RepeatWindow, NodeOrigin};
+pub use self::repeat::{repeat_iterators, virtual_repeats, instantiate_repeats, node_origins};
 
 use util::HasChildren;
 
+mod repeat;
+
 impl HasChildren for Node {
 
     fn children<'b>(&'b self) -> &'b [Node] {
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use super::{Node, NodeType, Template, View};
+use super::{RepeatData, RepeatBindingData};
+
+/// Instances of a repeat: `slots` instances showing the items
+/// from `first`. The instances of a virtual repeat are recycled
+/// when the window moves: an item is always shown by the
+/// instance `item % slots`, so that the ones still visible
+/// keep their instance.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct RepeatWindow {
+    pub first: usize,
+    pub slots: usize,
+}
+
+impl RepeatWindow {
+
+    /// Index of the item shown by the given instance.
+    /// A window without slots shows no item, `first` is returned.
+    pub fn item_of_slot(&self, slot: usize) -> usize {
+        if self.slots == 0 {
+            return self.first;
+        }
+        let shift = self.first % self.slots;
+        self.first + (slot + self.slots - shift) % self.slots
+    }
+}
+
+/// Where a node of an instantiated view comes from. Unlike its
+/// global index, it doesn't change when the view is instantiated
+/// again with other windows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NodeOrigin {
+    /// Node of the view, by its index in the view.
+    View(usize),
+    /// Node of the instance showing `item` in the `repeat`-th
+    /// repeat of the view, by its index in the instance.
+    Item { repeat: usize, item: usize, offset: usize },
+}
+
+/// Returns the `iter` key of every repeat of the view, in tree order.
+///
+/// Repeats declared inside a template are not returned:
+/// nested repeats are not supported yet.
+pub fn repeat_iterators(view: &View) -> Vec<String> {
+    let mut repeats = Vec::new();
+    collect_repeats(view, &mut repeats);
+    repeats.iter().map(|r| r.iter.clone()).collect()
+}
+
+/// Returns whether each repeat of the view is virtual,
+/// in the same order as `repeat_iterators`.
+pub fn virtual_repeats(view: &View) -> Vec<bool> {
+    let mut repeats = Vec::new();
+    collect_repeats(view, &mut repeats);
+    repeats.iter().map(|r| r.is_virtual).collect()
+}
+
+/// Create a copy of `view` where each repeat has as many children as
+/// given in `lengths` (in the order returned by `repeat_iterators`).
+/// For a virtual repeat, this is the number of slots of its window.
+///
+/// Each child is a group containing the children of the template
+/// named by the repeat. The data bindings inside are turned into
+/// `RepeatBinding`s that refer to the item they belong to.
+pub fn instantiate_repeats(
+    view: &View,
+    templates: &HashMap<String, Template>,
+    lengths: &[usize]) -> View
+{
+    let mut next_repeat = 0;
+    instantiate_node(view, templates, lengths, &mut next_repeat)
+}
+
+/// Returns the origin of every node of `instance`, indexed by global
+/// index. `windows` are the windows it was instantiated with.
+pub fn node_origins(instance: &View, windows: &[RepeatWindow]) -> Vec<NodeOrigin> {
+    let mut origins = Vec::new();
+    let mut next_view_node = 0;
+    let mut next_repeat = 0;
+    collect_origins(instance, windows, &mut next_view_node, &mut next_repeat, &mut origins);
+    origins
+}
+
+// ======================================== //
+//                  HELPERS                 //
+// ======================================== //
+
+fn collect_repeats<'a>(node: &'a Node, repeats: &mut Vec<&'a RepeatData>) {
+    if let NodeType::Repeat(ref repeat) = node.node_type {
+        repeats.push(repeat);
+    }
+
+    for child in node.children.iter() {
+        collect_repeats(child, repeats);
+    }
+}
+
+fn collect_origins(
+    node: &Node,
+    windows: &[RepeatWindow],
+    next_view_node: &mut usize,
+    next_repeat: &mut usize,
+    origins: &mut Vec<NodeOrigin>)
+{
+    origins.push(NodeOrigin::View(*next_view_node));
+    *next_view_node += 1;
+
+    if let NodeType::Repeat(_) = node.node_type {
+
+        let repeat = *next_repeat;
+        *next_repeat += 1;
+        let window = windows.get(repeat).map_or(RepeatWindow::default(), |&w| w);
+
+        for (slot, instance) in node.children.iter().enumerate() {
+            let start = origins.len();
+            collect_item_origins(instance, repeat, window.item_of_slot(slot), start, origins);
+        }
+
+    } else {
+
+        for child in node.children.iter() {
+            collect_origins(child, windows, next_view_node, next_repeat, origins);
+        }
+    }
+}
+
+fn collect_item_origins(
+    node: &Node,
+    repeat: usize,
+    item: usize,
+    start: usize,
+    origins: &mut Vec<NodeOrigin>)
+{
+    let offset = origins.len() - start;
+    origins.push(NodeOrigin::Item { repeat: repeat, item: item, offset: offset });
+
+    for child in node.children.iter() {
+        collect_item_origins(child, repeat, item, start, origins);
+    }
+}
+
+fn instantiate_node(
+    node: &Node,
+    templates: &HashMap<String, Template>,
+    lengths: &[usize],
+    next_repeat: &mut usize) -> Node
+{
+    let mut new_node = node.clone();
+
+    if let NodeType::Repeat(RepeatData { ref template_name, ref iter, .. }) = node.node_type {
+
+        let length = lengths.get(*next_repeat).map(|&l| l).unwrap_or(0);
+        *next_repeat += 1;
+
+        new_node.children = match templates.get(template_name) {
+            Some(template) => (0..length).map(|index| {
+                let mut instance = Node::from_template(template, NodeType::Group);
+                for child in instance.children.iter_mut() {
+                    scope_bindings(child, iter, index);
+                }
+                instance
+            }).collect(),
+            None => Vec::new(),
+        };
+
+    } else {
+
+        new_node.children = node.children.iter()
+            .map(|child| instantiate_node(child, templates, lengths, next_repeat))
+            .collect();
+    }
+
+    new_node
+}
+
+fn scope_bindings(node: &mut Node, iter: &String, index: usize) {
+    let scoped = match node.node_type {
+        NodeType::Binding(ref key) => Some(NodeType::RepeatBinding(RepeatBindingData {
+            iter: iter.clone(),
+            index: index,
+            key: key.clone(),
+        })),
+        _ => None
+    };
+
+    if let Some(node_type) = scoped {
+        node.node_type = node_type;
+    }
+
+    for child in node.children.iter_mut() {
+        scope_bindings(child, iter, index);
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::io::BufReader;
+    use oil_parsers::EmptyErrorReporter;
+    use markup::{self, NodeType, RepeatBindingData};
+    use super::NodeOrigin;
+
+    #[test]
+    fn instances_should_scope_their_bindings() {
+        let reader = BufReader::new(
+            "<view>\
+                <repeat iter=\"{{player.friends}}\" template-name=\"friend\"/>\
+             </view>\
+             <template name=\"friend\">\
+                <button>{{name}}</button>\
+             </template>".as_bytes());
+        let lib = markup::parse(EmptyErrorReporter, reader);
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+
+        assert_eq!(super::repeat_iterators(view), vec!["player.friends".to_string()]);
+
+        let instance = super::instantiate_repeats(view, &lib.templates, &[2]);
+        let repeat = &instance.children[0];
+
+        assert_eq!(repeat.children.len(), 2);
+        assert_eq!(repeat.children[1].node_type, NodeType::Group);
+        assert_eq!(repeat.children[1].children[0].children[0].node_type,
+            NodeType::RepeatBinding(RepeatBindingData {
+                iter: "player.friends".to_string(),
+                index: 1,
+                key: "name".to_string(),
+            }));
+    }
+
+    #[test]
+    fn origins_should_follow_the_window() {
+        let reader = BufReader::new(
+            "<view>\
+                <repeat iter=\"{{ auctions }}\" template-name=\"auction\" virtual=\"true\"/>\
+                <button/>\
+             </view>\
+             <template name=\"auction\">\
+                <button/>\
+             </template>".as_bytes());
+        let lib = markup::parse(EmptyErrorReporter, reader);
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+
+        let instance = super::instantiate_repeats(view, &lib.templates, &[2]);
+        let window = super::RepeatWindow { first: 3, slots: 2 };
+        let item = |item, offset| NodeOrigin::Item { repeat: 0, item: item, offset: offset };
+
+        assert_eq!(super::node_origins(&instance, &[window]), vec![
+            NodeOrigin::View(0),
+            NodeOrigin::View(1),
+            item(4, 0),
+            item(4, 1),
+            item(3, 0),
+            item(3, 1),
+            NodeOrigin::View(2),
+        ]);
+    }
+
+    #[test]
+    fn items_should_keep_their_slot_when_the_window_moves() {
+        let items = |first| {
+            let window = super::RepeatWindow { first: first, slots: 4 };
+            (0..4).map(|slot| window.item_of_slot(slot)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(items(0), vec![0, 1, 2, 3]);
+        assert_eq!(items(1), vec![4, 1, 2, 3]);
+        assert_eq!(items(6), vec![8, 9, 6, 7]);
+    }
+
+    #[test]
+    fn an_empty_window_should_not_divide_by_zero() {
+        let window = super::RepeatWindow::default();
+        assert_eq!(window.item_of_slot(0), 0);
+        assert_eq!(super::RepeatWindow { first: 3, slots: 0 }.item_of_slot(2), 3);
+    }
+}
@@ -3,26 +3,35 @@ use cgmath;
 
 use std::default::Default;
 use glium::{Surface, Display};
-use image::{
-    GenericImage
-};
+use glium::texture::CompressedTexture2d;
 
-use resource::ResourceManager;
+use resource::{ResourceManager, GlyphQuad};
 use Viewport;
 use RenderBackbend;
-use rendering;
+use rendering::{DisplayList, DrawCommand, Rect};
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(Vertex, position, tex_coords);
 
 pub struct GliumRenderer<'a> {
     display: &'a Display,
     index_buffer: glium::IndexBuffer,
-    program: glium::Program,
+    texture_program: glium::Program,
+    color_program: glium::Program,
+    text_program: glium::Program,
     matrix: cgmath::Matrix4<f32>,
+    viewport: Viewport,
 }
 
 impl<'a> GliumRenderer<'a> {
     pub fn new(display: &'a Display) -> GliumRenderer<'a> {
 
-        let program = glium::Program::from_source(display, r"
+        let texture_program = glium::Program::from_source(display, r"
             #version 110
 
             uniform mat4 matrix;
@@ -39,21 +48,117 @@ impl<'a> GliumRenderer<'a> {
         ", r"
             #version 110
             uniform sampler2D texture;
+            uniform float opacity;
             varying vec2 v_tex_coords;
 
             void main() {
                 vec3 gamma = vec3(2.2);
-                gl_FragColor = vec4(pow(texture2D(texture, v_tex_coords).rgb, gamma), 1);
+                vec4 texel = texture2D(texture, v_tex_coords);
+                gl_FragColor = vec4(pow(texel.rgb, gamma), texel.a * opacity);
             }
         ", None).unwrap();
 
+        let color_program = glium::Program::from_source(display, r"
+            #version 110
+
+            uniform mat4 matrix;
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = matrix * vec4(position, 0.0, 1.0);
+            }
+        ", r"
+            #version 110
+            uniform vec4 color;
+
+            void main() {
+                gl_FragColor = color;
+            }
+        ", None).unwrap();
+
+        // Glyph atlases are white, the coverage is in the alpha channel.
+        let text_program = glium::Program::from_source(display, r"
+            #version 110
+
+            uniform mat4 matrix;
+
+            attribute vec2 position;
+            attribute vec2 tex_coords;
+
+            varying vec2 v_tex_coords;
+
+            void main() {
+                gl_Position = matrix * vec4(position, 0.0, 1.0);
+                v_tex_coords = vec2(tex_coords.x, 1.0 - tex_coords.y);
+            }
+        ", r"
+            #version 110
+            uniform sampler2D texture;
+            uniform vec4 color;
+            varying vec2 v_tex_coords;
+
+            void main() {
+                gl_FragColor = vec4(color.rgb, color.a * texture2D(texture, v_tex_coords).a);
+            }
+        ", None).unwrap();
 
         GliumRenderer {
             display: display,
             index_buffer: glium::IndexBuffer::new(display,
                 glium::index::TriangleStrip(vec![1u32, 2, 0, 3])),
-            program: program,
-            matrix: cgmath::Matrix4::zero()
+            texture_program: texture_program,
+            color_program: color_program,
+            text_program: text_program,
+            matrix: cgmath::Matrix4::zero(),
+            viewport: Viewport { width: 0.0, height: 0.0 },
+        }
+    }
+
+    fn quad(&self, bounds: &Rect, tex_coords: &Rect) -> glium::VertexBuffer<Vertex> {
+        // TODO: Look how to do a glMapBuffer instead of creating a new
+        // buffer for each command.
+        // Note: for now  it should be acceptable as this is probably
+        //       not the bottle neck.
+        glium::VertexBuffer::new(self.display, quad_vertices(bounds, tex_coords).to_vec())
+    }
+
+    /// Buffers drawing all the glyphs of a text run at once,
+    /// as they all come from the atlas of the run's font.
+    fn glyph_quads(&self, glyphs: &[GlyphQuad])
+        -> (glium::VertexBuffer<Vertex>, glium::IndexBuffer)
+    {
+        let mut vertices = Vec::with_capacity(glyphs.len() * 4);
+        let mut indices = Vec::with_capacity(glyphs.len() * 6);
+
+        for glyph in glyphs {
+            let first = vertices.len() as u32;
+            vertices.extend(quad_vertices(&glyph.bounds, &glyph.tex_coords).iter().cloned());
+            // The two triangles of the strip used for a single quad.
+            indices.extend([1, 2, 0, 0, 2, 3].iter().map(|&i| first + i));
+        }
+
+        (glium::VertexBuffer::new(self.display, vertices),
+         glium::IndexBuffer::new(self.display, glium::index::TrianglesList(indices)))
+    }
+
+    fn draw_parameters(&self, clip: Option<&Rect>) -> glium::DrawParameters {
+        // The scissor box origin is the bottom left corner of the frame.
+        let scissor = clip.map(|c| glium::Rect {
+            left: c.x.max(0.0) as u32,
+            bottom: (self.viewport.height - c.y - c.height).max(0.0) as u32,
+            width: c.width.max(0.0) as u32,
+            height: c.height.max(0.0) as u32,
+        });
+
+        // Every command is blended over the previous ones.
+        glium::DrawParameters {
+            scissor: scissor,
+            blending_function: Some(glium::BlendingFunction::Addition {
+                source: glium::LinearBlendingFactor::SourceAlpha,
+                destination: glium::LinearBlendingFactor::OneMinusSourceAlpha,
+            }),
+            .. Default::default()
         }
     }
 }
@@ -61,40 +166,113 @@ impl<'a> GliumRenderer<'a> {
 impl<'a> RenderBackbend for GliumRenderer<'a> {
 
     type Frame = glium::Frame;
+    type Texture = CompressedTexture2d;
 
     fn prepare_frame(&mut self, vp: Viewport)
         -> <GliumRenderer as RenderBackbend>::Frame
     {
         let mut f = self.display.draw();
         self.matrix = cgmath::ortho(0.0, vp.width, vp.height, 0.0, 0.0, 1.0);
+        self.viewport = vp;
         f.clear_color(0.0, 0.0, 0.0, 0.0);
         f
     }
 
-    fn render_element<R>(
+    fn render_display_list<R>(
         &self,
         resource_manager: &R,
         frame: &mut <GliumRenderer as RenderBackbend>::Frame,
-        data: &rendering::RenderData)
-        where R: ResourceManager
+        list: &DisplayList)
+        where R: ResourceManager<Texture=CompressedTexture2d>
     {
-        let tex = resource_manager.get_texture(data.main_texture);
-        let uniforms = uniform! {
-            matrix: self.matrix,
-            texture: tex
-        };
-
-        let vb = data.vertex_coords_buffer.as_ref().unwrap();
-
-        frame.draw(
-            (vb, &data.tex_coords_buffer),
-            &self.index_buffer,
-            &self.program,
-            &uniforms,
-            &Default::default()).unwrap();
+        let mut clips: Vec<Rect> = Vec::new();
+
+        for command in list.iter() {
+            match *command {
+                DrawCommand::Image { texture, ref bounds, ref tex_coords, opacity } => {
+                    let tex = resource_manager.get_texture(texture);
+                    let uniforms = uniform! {
+                        matrix: self.matrix,
+                        texture: tex,
+                        opacity: opacity
+                    };
+
+                    frame.draw(
+                        &self.quad(bounds, tex_coords),
+                        &self.index_buffer,
+                        &self.texture_program,
+                        &uniforms,
+                        &self.draw_parameters(clips.last())).unwrap();
+                }
+                DrawCommand::Rect { ref bounds, color } => {
+                    let uniforms = uniform! {
+                        matrix: self.matrix,
+                        color: color
+                    };
+
+                    frame.draw(
+                        &self.quad(bounds, &Rect::default()),
+                        &self.index_buffer,
+                        &self.color_program,
+                        &uniforms,
+                        &self.draw_parameters(clips.last())).unwrap();
+                }
+                DrawCommand::Text(ref run) => {
+                    let font = resource_manager.get_font(run.font);
+                    let uniforms = uniform! {
+                        matrix: self.matrix,
+                        texture: resource_manager.get_font_atlas(run.font),
+                        color: run.color
+                    };
+                    let glyphs = font.layout(&run.text, run.bounds.x, run.bounds.y);
+
+                    if !glyphs.is_empty() {
+                        let (vertices, indices) = self.glyph_quads(&glyphs);
+                        frame.draw(
+                            &vertices,
+                            &indices,
+                            &self.text_program,
+                            &uniforms,
+                            &self.draw_parameters(clips.last())).unwrap();
+                    }
+                }
+                DrawCommand::PushClip(ref clip) => {
+                    let clip = match clips.last() {
+                        Some(current) => current.intersection(clip),
+                        None => *clip
+                    };
+                    clips.push(clip);
+                }
+                DrawCommand::PopClip => {
+                    clips.pop();
+                }
+            }
+        }
     }
 
     fn flush_frame(&self, frame: <GliumRenderer as RenderBackbend>::Frame) {
         frame.finish();
     }
 }
+
+fn quad_vertices(bounds: &Rect, tex_coords: &Rect) -> [Vertex; 4] {
+    let (x, y, xo, yo) = (
+        bounds.x,
+        bounds.y,
+        bounds.x + bounds.width,
+        bounds.y + bounds.height
+    );
+    let (u, v, uo, vo) = (
+        tex_coords.x,
+        tex_coords.y,
+        tex_coords.x + tex_coords.width,
+        tex_coords.y + tex_coords.height
+    );
+
+    [
+        Vertex { position: [ x,  y], tex_coords: [ u,  v] },
+        Vertex { position: [ x, yo], tex_coords: [ u, vo] },
+        Vertex { position: [xo, yo], tex_coords: [uo, vo] },
+        Vertex { position: [xo,  y], tex_coords: [uo,  v] }
+    ]
+}
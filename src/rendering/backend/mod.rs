@@ -1,4 +1,8 @@
 
+#[cfg(feature = "glium")]
 pub use self::glutinglium::GliumRenderer;
+pub use self::software::SoftwareRenderer;
 
+#[cfg(feature = "glium")]
 mod glutinglium;
+mod software;
@@ -0,0 +1,359 @@
+use std::cmp;
+
+use num::traits::ToPrimitive;
+use image::{Rgba, RgbaImage};
+
+use resource::{ResourceManager, GlyphQuad};
+use Viewport;
+use RenderBackbend;
+use rendering::{DisplayList, DrawCommand, Rect};
+
+/// A `RenderBackbend` that rasterizes the display list
+/// into an in-memory RGBA image. It doesn't need any
+/// window or GPU, which makes it a good fit for tests.
+///
+/// The frame returned by `prepare_frame` is the image itself,
+/// so `flush_frame` only drops it. If you want to keep the result
+/// around, don't call `flush_frame`.
+pub struct SoftwareRenderer;
+
+impl SoftwareRenderer {
+
+    pub fn new() -> SoftwareRenderer {
+        SoftwareRenderer
+    }
+}
+
+impl RenderBackbend for SoftwareRenderer {
+
+    type Frame = RgbaImage;
+    type Texture = RgbaImage;
+
+    fn prepare_frame(&mut self, vp: Viewport) -> RgbaImage {
+        RgbaImage::new(
+            vp.width.max(0.0).to_u32().unwrap(),
+            vp.height.max(0.0).to_u32().unwrap()
+        )
+    }
+
+    fn render_display_list<R>(
+        &self,
+        resource_manager: &R,
+        frame: &mut RgbaImage,
+        list: &DisplayList)
+        where R: ResourceManager<Texture=RgbaImage>
+    {
+        let (fw, fh) = frame.dimensions();
+        let mut clips = vec![Rect {
+            x: 0.0,
+            y: 0.0,
+            width: fw as f32,
+            height: fh as f32,
+        }];
+
+        for command in list.iter() {
+            let clip = *clips.last().unwrap();
+            match *command {
+                DrawCommand::Image { texture, ref bounds, ref tex_coords, opacity } => {
+                    let image = resource_manager.get_texture(texture);
+                    draw_image(frame, &clip, image, bounds, tex_coords, opacity);
+                }
+                DrawCommand::Rect { ref bounds, color } => {
+                    fill_rect(frame, &clip, bounds, color);
+                }
+                DrawCommand::Text(ref run) => {
+                    let font = resource_manager.get_font(run.font);
+                    let atlas = resource_manager.get_font_atlas(run.font);
+                    for glyph in font.layout(&run.text, run.bounds.x, run.bounds.y) {
+                        draw_glyph(frame, &clip, atlas, &glyph, run.color);
+                    }
+                }
+                DrawCommand::PushClip(ref rect) => {
+                    clips.push(clip.intersection(rect));
+                }
+                DrawCommand::PopClip => {
+                    // The first clip rect is the frame itself.
+                    if clips.len() > 1 {
+                        clips.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    fn flush_frame(&self, _: RgbaImage) {
+        // Nothing to do.
+    }
+}
+
+// ======================================== //
+//                  HELPERS                 //
+// ======================================== //
+
+/// Returns the range of pixels whose center lies in [start, start + length),
+/// limited to the range [0, max).
+fn pixel_span(start: f32, length: f32, max: u32) -> (u32, u32) {
+    let first = (start - 0.5).ceil().max(0.0);
+    let last = (start + length - 0.5).ceil().max(0.0);
+    (first.min(max as f32) as u32, last.min(max as f32) as u32)
+}
+
+fn fill_rect(frame: &mut RgbaImage, clip: &Rect, bounds: &Rect, color: [f32; 4]) {
+    if color[3] <= 0.0 {
+        return;
+    }
+
+    let (fw, fh) = frame.dimensions();
+    let area = clip.intersection(bounds);
+    let (x0, x1) = pixel_span(area.x, area.width, fw);
+    let (y0, y1) = pixel_span(area.y, area.height, fh);
+
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let blended = blend(*frame.get_pixel(x, y), color, color[3]);
+            frame.put_pixel(x, y, blended);
+        }
+    }
+}
+
+fn draw_image(
+    frame: &mut RgbaImage,
+    clip: &Rect,
+    image: &RgbaImage,
+    bounds: &Rect,
+    tex_coords: &Rect,
+    opacity: f32)
+{
+    if bounds.width <= 0.0 || bounds.height <= 0.0 {
+        return;
+    }
+
+    let (fw, fh) = frame.dimensions();
+    let (iw, ih) = image.dimensions();
+    if iw == 0 || ih == 0 {
+        return;
+    }
+
+    let area = clip.intersection(bounds);
+    let (x0, x1) = pixel_span(area.x, area.width, fw);
+    let (y0, y1) = pixel_span(area.y, area.height, fh);
+
+    for y in y0..y1 {
+        // Nearest texel of the pixel center.
+        let v = tex_coords.y
+            + (y as f32 + 0.5 - bounds.y) / bounds.height * tex_coords.height;
+        let ty = cmp::min((v * ih as f32).floor().max(0.0) as u32, ih - 1);
+
+        for x in x0..x1 {
+            let u = tex_coords.x
+                + (x as f32 + 0.5 - bounds.x) / bounds.width * tex_coords.width;
+            let tx = cmp::min((u * iw as f32).floor().max(0.0) as u32, iw - 1);
+
+            let texel = image.get_pixel(tx, ty).data;
+            let alpha = texel[3] as f32 / 255.0 * opacity;
+            if alpha > 0.0 {
+                let color = [
+                    texel[0] as f32 / 255.0,
+                    texel[1] as f32 / 255.0,
+                    texel[2] as f32 / 255.0,
+                    1.0,
+                ];
+                let blended = blend(*frame.get_pixel(x, y), color, alpha);
+                frame.put_pixel(x, y, blended);
+            }
+        }
+    }
+}
+
+/// Draw a glyph of a font atlas: the alpha channel of the atlas
+/// is used as coverage to blend `color` over the frame.
+fn draw_glyph(
+    frame: &mut RgbaImage,
+    clip: &Rect,
+    atlas: &RgbaImage,
+    glyph: &GlyphQuad,
+    color: [f32; 4])
+{
+    let (fw, fh) = frame.dimensions();
+    let (aw, ah) = atlas.dimensions();
+    let bounds = &glyph.bounds;
+    let tex_coords = &glyph.tex_coords;
+    if bounds.width <= 0.0 || bounds.height <= 0.0 || aw == 0 || ah == 0 {
+        return;
+    }
+
+    let area = clip.intersection(bounds);
+    let (x0, x1) = pixel_span(area.x, area.width, fw);
+    let (y0, y1) = pixel_span(area.y, area.height, fh);
+
+    for y in y0..y1 {
+        let v = tex_coords.y
+            + (y as f32 + 0.5 - bounds.y) / bounds.height * tex_coords.height;
+        let ty = cmp::min((v * ah as f32).floor().max(0.0) as u32, ah - 1);
+
+        for x in x0..x1 {
+            let u = tex_coords.x
+                + (x as f32 + 0.5 - bounds.x) / bounds.width * tex_coords.width;
+            let tx = cmp::min((u * aw as f32).floor().max(0.0) as u32, aw - 1);
+
+            let coverage = atlas.get_pixel(tx, ty).data[3] as f32 / 255.0 * color[3];
+            if coverage > 0.0 {
+                let blended = blend(*frame.get_pixel(x, y), color, coverage);
+                frame.put_pixel(x, y, blended);
+            }
+        }
+    }
+}
+
+/// Blend `color` over `dst`, `alpha` being the opacity of `color`.
+fn blend(dst: Rgba<u8>, color: [f32; 4], alpha: f32) -> Rgba<u8> {
+    let d = |i: usize| dst.data[i] as f32 / 255.0;
+    let out_alpha = alpha + d(3) * (1.0 - alpha);
+    let c = |v: f32| (v.max(0.0).min(1.0) * 255.0).round() as u8;
+    Rgba([
+        c(color[0] * alpha + d(0) * (1.0 - alpha)),
+        c(color[1] * alpha + d(1) * (1.0 - alpha)),
+        c(color[2] * alpha + d(2) * (1.0 - alpha)),
+        c(out_alpha),
+    ])
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::path::Path;
+    use super::SoftwareRenderer;
+    use image::{Rgba, RgbaImage};
+    use resource::{self, BasicResourceManager};
+    use rendering::{DisplayList, DrawCommand, Rect, TextRun};
+    use RenderBackbend;
+    use Viewport;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect { x: x, y: y, width: width, height: height }
+    }
+
+    #[test]
+    fn fill_rect_should_only_cover_its_bounds() {
+        let rm = resource::create_software_manager();
+        let mut renderer = SoftwareRenderer::new();
+        let mut frame = renderer.prepare_frame(Viewport { width: 4.0, height: 4.0 });
+        let mut list = DisplayList::new();
+        list.push(DrawCommand::Rect {
+            bounds: rect(1.0, 1.0, 2.0, 2.0),
+            color: [1.0, 0.0, 0.0, 1.0],
+        });
+
+        renderer.render_display_list(&rm, &mut frame, &list);
+
+        assert_eq!(*frame.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(*frame.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(2, 2), Rgba([255, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(3, 3), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn image_should_sample_the_texture_sub_rect() {
+        let mut rm = resource::create_software_manager();
+        let mut img = RgbaImage::new(2, 1);
+        img.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        img.put_pixel(1, 0, Rgba([0, 255, 0, 255]));
+        let id = rm.insert_image(img);
+
+        let mut renderer = SoftwareRenderer::new();
+        let mut frame = renderer.prepare_frame(Viewport { width: 4.0, height: 2.0 });
+        let mut list = DisplayList::new();
+        list.push(DrawCommand::Image {
+            texture: id,
+            bounds: rect(0.0, 0.0, 4.0, 2.0),
+            tex_coords: rect(0.5, 0.0, 0.5, 1.0),
+            opacity: 1.0,
+        });
+
+        renderer.render_display_list(&rm, &mut frame, &list);
+
+        for (_, _, p) in frame.enumerate_pixels() {
+            assert_eq!(*p, Rgba([0, 255, 0, 255]));
+        }
+    }
+
+    #[test]
+    fn translucent_commands_should_be_blended() {
+        let mut rm = resource::create_software_manager();
+        let mut img = RgbaImage::new(2, 1);
+        img.put_pixel(0, 0, Rgba([0, 0, 255, 255]));
+        img.put_pixel(1, 0, Rgba([0, 0, 255, 0]));
+        let id = rm.insert_image(img);
+
+        let mut renderer = SoftwareRenderer::new();
+        let mut frame = renderer.prepare_frame(Viewport { width: 2.0, height: 1.0 });
+        let mut list = DisplayList::new();
+        list.push(DrawCommand::Rect {
+            bounds: rect(0.0, 0.0, 2.0, 1.0),
+            color: [1.0, 0.0, 0.0, 1.0],
+        });
+        list.push(DrawCommand::Image {
+            texture: id,
+            bounds: rect(0.0, 0.0, 2.0, 1.0),
+            tex_coords: rect(0.0, 0.0, 1.0, 1.0),
+            opacity: 0.5,
+        });
+
+        renderer.render_display_list(&rm, &mut frame, &list);
+
+        assert_eq!(*frame.get_pixel(0, 0), Rgba([128, 0, 128, 255]));
+        // Fully transparent texels leave the frame untouched.
+        assert_eq!(*frame.get_pixel(1, 0), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn clip_should_restrict_drawing() {
+        let rm = resource::create_software_manager();
+        let mut renderer = SoftwareRenderer::new();
+        let mut frame = renderer.prepare_frame(Viewport { width: 4.0, height: 1.0 });
+        let mut list = DisplayList::new();
+        list.push(DrawCommand::PushClip(rect(0.0, 0.0, 2.0, 1.0)));
+        list.push(DrawCommand::Rect {
+            bounds: rect(0.0, 0.0, 4.0, 1.0),
+            color: [1.0, 1.0, 1.0, 1.0],
+        });
+        list.push(DrawCommand::PopClip);
+
+        renderer.render_display_list(&rm, &mut frame, &list);
+
+        assert_eq!(*frame.get_pixel(1, 0), Rgba([255, 255, 255, 255]));
+        assert_eq!(*frame.get_pixel(2, 0), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn text_should_be_drawn_with_its_color() {
+        let mut rm = resource::create_software_manager();
+        let font = rm.get_font_id(&Path::new("examples/DejaVuSansMono.ttf"), 16.0, 16.0)
+            .unwrap();
+        let mut renderer = SoftwareRenderer::new();
+        let mut frame = renderer.prepare_frame(Viewport { width: 32.0, height: 32.0 });
+        let mut list = DisplayList::new();
+        list.push(DrawCommand::Text(TextRun {
+            text: "#".to_string(),
+            bounds: rect(0.0, 0.0, 32.0, 32.0),
+            font: font,
+            color: [1.0, 0.0, 0.0, 1.0],
+        }));
+
+        renderer.render_display_list(&rm, &mut frame, &list);
+
+        let mut covered = 0;
+        for (_, _, p) in frame.enumerate_pixels() {
+            if p.data[3] > 0 {
+                covered += 1;
+                assert_eq!(p.data[1], 0);
+                assert_eq!(p.data[2], 0);
+            }
+        }
+        assert!(covered > 0);
+    }
+}
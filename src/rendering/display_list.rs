@@ -0,0 +1,84 @@
+use std::ops::Deref;
+
+use resource::ResourceId;
+use layout::Rect;
+
+/// A draw command is the smallest unit of work that a
+/// backend has to perform. All coordinates are in pixels,
+/// relative to the top left corner of the viewport.
+///
+/// Commands are blended over what has been drawn before
+/// using their alpha channel.
+#[derive(Clone)]
+pub enum DrawCommand {
+    /// Draw the sub-rectangle `tex_coords` of the texture `texture`
+    /// stretched over `bounds`.
+    ///
+    /// `tex_coords` is expressed in normalized coordinates, the origin
+    /// being the top left corner of the image. The alpha channel
+    /// of the texture is multiplied by `opacity`.
+    Image {
+        texture: ResourceId,
+        bounds: Rect,
+        tex_coords: Rect,
+        opacity: f32,
+    },
+    /// Fill `bounds` with the given color.
+    /// The color components are in the range [0, 1] (rgba order).
+    Rect {
+        bounds: Rect,
+        color: [f32; 4],
+    },
+    /// Draw a run of text.
+    Text(TextRun),
+    /// Restrict all the subsequent commands to the given rectangle.
+    /// The clip rectangle is intersected with the current one if any.
+    PushClip(Rect),
+    /// Restore the clip rectangle in use before the last `PushClip`.
+    PopClip,
+}
+
+/// A single line of text, drawn with the glyphs of the
+/// font `font` (see `ResourceManager::get_font`).
+#[derive(Clone)]
+pub struct TextRun {
+    pub text: String,
+    pub bounds: Rect,
+    pub font: ResourceId,
+    /// Same format as the color of `DrawCommand::Rect`.
+    pub color: [f32; 4],
+}
+
+/// List of draw commands produced by a `View` (or a `Router`).
+/// Commands must be executed in order.
+pub struct DisplayList {
+    commands: Vec<DrawCommand>,
+}
+
+impl Deref for DisplayList {
+    type Target = [DrawCommand];
+
+    fn deref<'a>(&'a self) -> &'a [DrawCommand] {
+        self.commands.deref()
+    }
+}
+
+impl DisplayList {
+
+    pub fn new() -> DisplayList {
+        DisplayList {
+            commands: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, command: DrawCommand) {
+        self.commands.push(command);
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+}
+
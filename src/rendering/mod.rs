@@ -4,14 +4,20 @@
 
 pub mod backend;
 pub use self::view::View;
+pub use self::display_list::{DisplayList, DrawCommand, TextRun};
+pub use layout::Rect;
 
 mod view;
 mod render;
+mod display_list;
 
 #[derive(Copy, Clone, Debug)]
 pub enum TextureRule {
     Fit,
-    Repeat
+    Repeat,
+    /// Nine-slice scaling: the corners keep their size,
+    /// the edges and the center are stretched.
+    Slice,
 }
 
 // ======================================== //
@@ -19,107 +25,417 @@ pub enum TextureRule {
 // ======================================== //
 
 use num::traits::ToPrimitive;
-use glium;
-use glium::Display;
-use image::{GenericImage};
 
-use oil_shared::asset::ImageData;
+use oil_shared::asset::{ImageData, SliceInsets};
+use oil_shared::style::Unit;
 use layout::LayoutBox;
-use resource::{ResourceManager, ResourceId};
+use resource::{BasicResourceManager, ResourceId};
+use state::StateData;
 
-#[derive(Copy, Clone)]
-struct TexCoords {
-    tex_coords: [f32; 2],
+/// Background and border of a node. The background
+/// is a solid color with an image drawn over it, the
+/// border is drawn last, in the border area of the node.
+pub struct RenderData {
+    background_color: Option<[f32; 4]>,
+    background_image: Option<Sprite>,
+    rule: TextureRule,
+    border_color: Option<[f32; 4]>,
+    border_image: Option<Sprite>,
+    // Border widths computed by the layout
+    border: SliceInsets,
+    // Border box of the node
+    bounds: Rect,
+    // Includes the opacity of the ancestors
+    opacity: f32,
 }
 
-#[derive(Copy, Clone)]
-struct Vertex {
-    position: [f32; 2],
+/// An image ready to be drawn.
+struct Sprite {
+    texture: ResourceId,
+    tex_coords: Rect,
+    // Size in pixels of the image sub-rect
+    width: f32,
+    height: f32,
+    slice: Option<SliceInsets>,
 }
 
-implement_vertex!(Vertex, position);
-implement_vertex!(TexCoords, tex_coords);
+impl Sprite {
 
-pub struct RenderData {
-    main_texture: ResourceId,
-    tex_coords_buffer: glium::VertexBuffer<TexCoords>,
-    vertex_coords_buffer: Option<glium::VertexBuffer<Vertex>>,
-    rule: TextureRule,
+    fn new<R: BasicResourceManager>(resource_manager: &R, image: &ImageData) -> Sprite {
+        let (iw, ih) = resource_manager.get_image_dimensions(image.img);
+        let (w_m, h_m) = (iw.to_f32().unwrap(), ih.to_f32().unwrap());
+
+        Sprite {
+            texture: image.img,
+            tex_coords: Rect {
+                x: image.offset_x / w_m,
+                y: image.offset_y / h_m,
+                width: image.width / w_m,
+                height: image.height / h_m,
+            },
+            width: image.width,
+            height: image.height,
+            slice: image.slice,
+        }
+    }
 }
 
 impl RenderData {
 
-    fn new<R: ResourceManager>(
-        display: &Display,
-        resource_manager: &R,
-        image: ImageData,
-        rule: TextureRule)
-        -> RenderData
-    {
-
-        let buffer = RenderData::initialize_buffer(display, resource_manager, &image);
-
+    fn new() -> RenderData {
         RenderData {
-            main_texture: image.img,
-            tex_coords_buffer: buffer,
-            vertex_coords_buffer: None,
-            rule: rule,
+            background_color: None,
+            background_image: None,
+            rule: TextureRule::Fit,
+            border_color: None,
+            border_image: None,
+            border: SliceInsets::uniform(0f32),
+            bounds: Rect::default(),
+            opacity: 1f32,
         }
     }
 
-    fn initialize_buffer<R: ResourceManager>(
-        display: &Display,
-        resource_manager: &R,
-        image: &ImageData)
-        -> glium::VertexBuffer<TexCoords>
-    {
-        // TODO: Handle TextureRule::Repeat
-        let (iw, ih) = resource_manager.get_image_dimensions(image.img);
-        let (w_m, h_m) = (iw.to_f32().unwrap(), ih.to_f32().unwrap());
-        let x = image.offset_x / w_m;
-        let y = image.offset_y / h_m;
-        let xo = image.width  / w_m + x;
-        let yo = image.height / h_m + y;
-
-        glium::VertexBuffer::new(display,
-            vec![
-                TexCoords { tex_coords: [ x, y ] },
-                TexCoords { tex_coords: [ x, yo] },
-                TexCoords { tex_coords: [xo, yo] },
-                TexCoords { tex_coords: [xo, y ] }
-            ]
-        )
-    }
-
-    fn update_texture<R: ResourceManager>(
+    fn update_from_state<R: BasicResourceManager>(
         &mut self,
-        display: &Display,
         rm: &R,
-        image: ImageData)
+        state: &StateData,
+        opacity: f32)
     {
+        self.opacity = opacity;
+        self.background_color = state.get_background_color().map(|c| c.to_f32());
+        self.rule = state.get_background_rule().unwrap_or(TextureRule::Fit);
+        self.background_image = state.get_background_image().map(|image| {
+            let mut sprite = Sprite::new(rm, &image);
+            if let Some(inset) = state.get_background_slice() {
+                // A percentage is relative to the size of the image.
+                let resolve = |size: f32| match inset.unit {
+                    Unit::Percent => inset.value / 100f32 * size,
+                    _ => inset.value,
+                };
+                sprite.slice = Some(SliceInsets {
+                    left: resolve(sprite.width),
+                    right: resolve(sprite.width),
+                    top: resolve(sprite.height),
+                    bottom: resolve(sprite.height),
+                });
+            }
+            sprite
+        });
+        self.border_color = state.get_border_color().map(|c| c.to_f32());
+        self.border_image = state.get_border_image().map(|image| Sprite::new(rm, &image));
+    }
+
+    fn update_coords(&mut self, lb: &LayoutBox) {
+        let d = lb.dim();
+        self.bounds = d.border_box();
+        self.border = SliceInsets {
+            left: d.border.left,
+            right: d.border.right,
+            top: d.border.top,
+            bottom: d.border.bottom,
+        };
+    }
 
-        // TODO: Update existing buffer instead
-        self.tex_coords_buffer = RenderData::initialize_buffer(display, rm, &image);
-        self.main_texture = image.img;
-    }
-
-    fn update_coords(&mut self, display: &Display, lb: &LayoutBox) {
-        // TODO: Look how to do a glMapBuffer instead of this when
-        // vertex_coords_buffer is Some(buffer).
-        // Note: for now  it should be acceptable as this is probably
-        //       not the bottle neck.
-        let x = lb.dim().content.x + lb.dim().margin.left;
-        let y = lb.dim().content.y + lb.dim().margin.top;
-        let height = lb.dim().content.height;
-        let width = lb.dim().content.width;
-
-        self.vertex_coords_buffer = Some(
-            glium::VertexBuffer::new(display, vec![
-                Vertex { position: [         x, y         ]},
-                Vertex { position: [         x, y + height]},
-                Vertex { position: [ x + width, y + height]},
-                Vertex { position: [ x + width, y         ]}
-            ])
+    fn push_commands(&self, list: &mut DisplayList) {
+        if self.opacity <= 0.0 {
+            return;
+        }
+        if let Some(color) = self.background_color {
+            self.push_rect(list, self.bounds, color);
+        }
+        if let Some(ref sprite) = self.background_image {
+            match self.rule {
+                TextureRule::Fit => {
+                    self.push_image(list, sprite, self.bounds, sprite.tex_coords);
+                }
+                // One command per tile: the image might be a sub-rect
+                // of an atlas, so backends can't wrap the texture.
+                TextureRule::Repeat => {
+                    let tiles = repeat_tiles(
+                        &self.bounds,
+                        &sprite.tex_coords,
+                        sprite.width,
+                        sprite.height
+                    );
+                    for (bounds, tex_coords) in tiles {
+                        self.push_image(list, sprite, bounds, tex_coords);
+                    }
+                }
+                TextureRule::Slice => {
+                    let insets = sprite.slice.unwrap_or(SliceInsets::uniform(0f32));
+                    let slices = nine_slices(&self.bounds, &insets, sprite, &insets, true);
+                    for (bounds, tex_coords) in slices {
+                        self.push_image(list, sprite, bounds, tex_coords);
+                    }
+                }
+            }
+        }
+        if let Some(color) = self.border_color {
+            for edge in border_edges(&self.bounds, &self.border).iter() {
+                self.push_rect(list, *edge, color);
+            }
+        }
+        // The image is cut in nine parts like with the `slice` rule,
+        // its corners are stretched to the border widths.
+        if let Some(ref sprite) = self.border_image {
+            let insets = sprite.slice.unwrap_or(self.border);
+            let slices = nine_slices(&self.bounds, &self.border, sprite, &insets, false);
+            for (bounds, tex_coords) in slices {
+                self.push_image(list, sprite, bounds, tex_coords);
+            }
+        }
+    }
+
+    fn push_rect(&self, list: &mut DisplayList, bounds: Rect, color: [f32; 4]) {
+        list.push(DrawCommand::Rect {
+            bounds: bounds,
+            color: [color[0], color[1], color[2], color[3] * self.opacity],
+        });
+    }
+
+    fn push_image(&self, list: &mut DisplayList, sprite: &Sprite, bounds: Rect, tex_coords: Rect) {
+        list.push(DrawCommand::Image {
+            texture: sprite.texture,
+            bounds: bounds,
+            tex_coords: tex_coords,
+            opacity: self.opacity,
+        });
+    }
+}
+
+/// Returns the top, bottom, left and right edges of
+/// the border of `bounds`. The top and bottom edges
+/// include the corners.
+fn border_edges(bounds: &Rect, border: &SliceInsets) -> Vec<Rect> {
+    let inner_height = bounds.height - border.top - border.bottom;
+    let edges = [
+        Rect { x: bounds.x, y: bounds.y, width: bounds.width, height: border.top },
+        Rect {
+            x: bounds.x,
+            y: bounds.y + bounds.height - border.bottom,
+            width: bounds.width,
+            height: border.bottom
+        },
+        Rect { x: bounds.x, y: bounds.y + border.top, width: border.left, height: inner_height },
+        Rect {
+            x: bounds.x + bounds.width - border.right,
+            y: bounds.y + border.top,
+            width: border.right,
+            height: inner_height
+        },
+    ];
+
+    edges.iter().cloned().filter(|e| e.width > 0.0 && e.height > 0.0).collect()
+}
+
+/// Cut `bounds` along `insets` and the image of `sprite` along
+/// `image_insets` (in pixels of the image) in nine parts.
+/// The corners of `bounds` keep their size unless `bounds` is too small
+/// to hold them, in which case they are shrunk. The center is only
+/// returned if `with_center` is true.
+fn nine_slices(
+    bounds: &Rect,
+    insets: &SliceInsets,
+    sprite: &Sprite,
+    image_insets: &SliceInsets,
+    with_center: bool)
+    -> Vec<(Rect, Rect)>
+{
+    let mut slices = Vec::with_capacity(9);
+    if sprite.width <= 0.0 || sprite.height <= 0.0 {
+        return slices;
+    }
+
+    // Returns the (start, length) of the three parts of
+    // a segment, given the size of its two ends.
+    let cut = |start: f32, length: f32, a: f32, b: f32| {
+        let scale = if a + b > length { length / (a + b) } else { 1f32 };
+        let (a, b) = (a * scale, b * scale);
+        [(start, a), (start + a, length - a - b), (start + length - b, b)]
+    };
+
+    let tex_coords = &sprite.tex_coords;
+    let columns = cut(bounds.x, bounds.width, insets.left, insets.right);
+    let rows = cut(bounds.y, bounds.height, insets.top, insets.bottom);
+    let tex_columns = cut(
+        tex_coords.x,
+        tex_coords.width,
+        tex_coords.width * image_insets.left / sprite.width,
+        tex_coords.width * image_insets.right / sprite.width
+    );
+    let tex_rows = cut(
+        tex_coords.y,
+        tex_coords.height,
+        tex_coords.height * image_insets.top / sprite.height,
+        tex_coords.height * image_insets.bottom / sprite.height
+    );
+
+    for (i, (&(y, height), &(v, tex_height))) in rows.iter().zip(tex_rows.iter()).enumerate() {
+        for (j, (&(x, width), &(u, tex_width))) in columns.iter().zip(tex_columns.iter()).enumerate() {
+            if !with_center && i == 1 && j == 1 {
+                continue;
+            }
+            if width > 0.0 && height > 0.0 {
+                slices.push((
+                    Rect { x: x, y: y, width: width, height: height },
+                    Rect { x: u, y: v, width: tex_width, height: tex_height }
+                ));
+            }
+        }
+    }
+
+    slices
+}
+
+/// Cover `bounds` with tiles of `tile_width` x `tile_height` pixels
+/// showing `tex_coords`, starting from the top left corner.
+/// The tiles on the right and bottom edges only show the part
+/// of `tex_coords` that fits in `bounds`.
+fn repeat_tiles(bounds: &Rect, tex_coords: &Rect, tile_width: f32, tile_height: f32)
+    -> Vec<(Rect, Rect)>
+{
+    let mut tiles = Vec::new();
+    if tile_width <= 0.0 || tile_height <= 0.0 {
+        return tiles;
+    }
+
+    let mut y = 0f32;
+    while y < bounds.height {
+        let height = tile_height.min(bounds.height - y);
+        let mut x = 0f32;
+        while x < bounds.width {
+            let width = tile_width.min(bounds.width - x);
+            tiles.push((
+                Rect {
+                    x: bounds.x + x,
+                    y: bounds.y + y,
+                    width: width,
+                    height: height,
+                },
+                Rect {
+                    x: tex_coords.x,
+                    y: tex_coords.y,
+                    width: tex_coords.width * width / tile_width,
+                    height: tex_coords.height * height / tile_height,
+                }
+            ));
+            x += tile_width;
+        }
+        y += tile_height;
+    }
+
+    tiles
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use oil_shared::asset::SliceInsets;
+    use oil_shared::resource::new_resource_id;
+    use super::{Rect, Sprite, repeat_tiles, nine_slices, border_edges};
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect { x: x, y: y, width: width, height: height }
+    }
+
+    // A 20x20 sprite
+    fn sprite(tex_coords: Rect) -> Sprite {
+        Sprite {
+            texture: unsafe { new_resource_id(0) },
+            tex_coords: tex_coords,
+            width: 20.0,
+            height: 20.0,
+            slice: None,
+        }
+    }
+
+    #[test]
+    fn repeat_should_cut_the_tiles_on_the_edges() {
+        let tiles = repeat_tiles(
+            &rect(10.0, 20.0, 25.0, 10.0),
+            &rect(0.5, 0.25, 0.5, 0.5),
+            10.0,
+            10.0
+        );
+
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(tiles[0], (rect(10.0, 20.0, 10.0, 10.0), rect(0.5, 0.25, 0.5, 0.5)));
+        assert_eq!(tiles[1], (rect(20.0, 20.0, 10.0, 10.0), rect(0.5, 0.25, 0.5, 0.5)));
+        assert_eq!(tiles[2], (rect(30.0, 20.0, 5.0, 10.0), rect(0.5, 0.25, 0.25, 0.5)));
+    }
+
+    #[test]
+    fn slice_should_keep_the_corners_unscaled() {
+        let insets = SliceInsets::uniform(5.0);
+        let slices = nine_slices(
+            &rect(0.0, 0.0, 100.0, 50.0),
+            &insets,
+            &sprite(rect(0.0, 0.5, 1.0, 0.5)),
+            &insets,
+            true
         );
+
+        assert_eq!(slices.len(), 9);
+        // Top left corner
+        assert_eq!(slices[0], (rect(0.0, 0.0, 5.0, 5.0), rect(0.0, 0.5, 0.25, 0.125)));
+        // Center
+        assert_eq!(slices[4], (rect(5.0, 5.0, 90.0, 40.0), rect(0.25, 0.625, 0.5, 0.25)));
+        // Bottom right corner
+        assert_eq!(slices[8], (rect(95.0, 45.0, 5.0, 5.0), rect(0.75, 0.875, 0.25, 0.125)));
+    }
+
+    #[test]
+    fn slice_should_shrink_the_corners_of_small_nodes() {
+        let insets = SliceInsets::uniform(5.0);
+        let slices = nine_slices(
+            &rect(0.0, 0.0, 5.0, 20.0),
+            &insets,
+            &sprite(rect(0.0, 0.0, 1.0, 1.0)),
+            &insets,
+            true
+        );
+
+        // The middle column is empty.
+        assert_eq!(slices.len(), 6);
+        assert_eq!(slices[0].0, rect(0.0, 0.0, 2.5, 5.0));
+        assert_eq!(slices[1].0, rect(2.5, 0.0, 2.5, 5.0));
+    }
+
+    #[test]
+    fn border_image_should_skip_the_center() {
+        let slices = nine_slices(
+            &rect(0.0, 0.0, 100.0, 50.0),
+            &SliceInsets::uniform(2.0),
+            &sprite(rect(0.0, 0.0, 1.0, 1.0)),
+            &SliceInsets::uniform(5.0),
+            false
+        );
+
+        assert_eq!(slices.len(), 8);
+        // Corners are stretched to the border width.
+        assert_eq!(slices[0], (rect(0.0, 0.0, 2.0, 2.0), rect(0.0, 0.0, 0.25, 0.25)));
+        assert_eq!(slices[4].0, rect(98.0, 2.0, 2.0, 46.0));
+    }
+
+    #[test]
+    fn border_edges_should_cover_the_border_area() {
+        let border = SliceInsets { left: 1.0, right: 2.0, top: 3.0, bottom: 0.0 };
+        let edges = border_edges(&rect(10.0, 10.0, 20.0, 10.0), &border);
+
+        assert_eq!(edges, vec![
+            rect(10.0, 10.0, 20.0, 3.0),
+            rect(10.0, 13.0, 1.0, 7.0),
+            rect(28.0, 13.0, 2.0, 7.0),
+        ]);
+    }
+
+    #[test]
+    fn repeat_should_ignore_empty_images() {
+        assert!(repeat_tiles(&rect(0.0, 0.0, 10.0, 10.0), &rect(0.0, 0.0, 1.0, 1.0), 0.0, 10.0)
+            .is_empty());
     }
 }
@@ -1,14 +1,13 @@
-use std::ops::Deref;
-use glium::Display;
+use std::ops::{Deref, Range};
 
 use layout::LayoutBuffer;
-use resource::ResourceManager;
+use resource::BasicResourceManager;
 use util::BufferFromTree;
 use state::StateBuffer;
 use state::StateData;
 
 use super::RenderData;
-use super::TextureRule;
+use super::DisplayList;
 
 pub struct RenderBuffer {
     render_data: BufferFromTree<RenderData>,
@@ -25,59 +24,95 @@ impl Deref for RenderBuffer {
 impl RenderBuffer {
 
     pub fn new<R>(
-        display: &Display,
         resource_manager: &R,
         state_buffer: &StateBuffer) -> RenderBuffer
-        where R: ResourceManager
+        where R: BasicResourceManager
     {
         let node_producer = |state: &StateData| {
-            if let Some(img) = state.get_background_image() {
-                let rule = state.get_background_rule().unwrap_or(TextureRule::Fit);
-                Some(RenderData::new(
-                    display,
-                    resource_manager,
-                    img,
-                    rule
-                ))
+            if state.has_decorations() {
+                Some(RenderData::new())
             } else {
                 None
             }
         };
 
-        RenderBuffer {
+        let mut buffer = RenderBuffer {
             render_data: BufferFromTree::from_buffer(
                 state_buffer,
                 node_producer
             )
-        }
+        };
+        buffer.update_from_state(resource_manager, state_buffer);
+        buffer
     }
 
-    pub fn update_from_state<R: ResourceManager>(
+    pub fn update_from_state<R: BasicResourceManager>(
         &mut self,
-        display: &Display,
         resource_manager: &R,
         state_data: &StateBuffer)
     {
+        let opacities = state_data.opacities();
 
         for (&i, data) in self.render_data.enumerate_lookup_indices_mut().unwrap() {
 
             let state = unsafe { state_data.get_unchecked(i) };
 
-            if let Some(img) = state.get_background_image() {
-
-                data.update_texture(display, resource_manager, img);
-            }
+            data.update_from_state(resource_manager, state, opacities[i]);
         }
     }
 
-    pub fn update_from_layout(&mut self, display: &Display, layout_data: &LayoutBuffer) {
+    pub fn update_from_layout(&mut self, layout_data: &LayoutBuffer) {
 
         for (&i, data) in self.render_data.enumerate_lookup_indices_mut().unwrap() {
             // This part is always safe because the initialization step
             // ensure that:
             //       self.layout_data.len() >= self.render_data
             let boxi = unsafe { layout_data.get_unchecked(i) };
-            data.update_coords(display, &boxi);
+            data.update_coords(&boxi);
+        }
+    }
+
+    /// Same as `update_from_state`, for the nodes
+    /// of the given subtrees only.
+    pub fn update_from_state_in<R: BasicResourceManager>(
+        &mut self,
+        resource_manager: &R,
+        state_data: &StateBuffer,
+        subtrees: &[Range<usize>])
+    {
+        let opacities = state_data.opacities();
+
+        for subtree in subtrees {
+            let nodes = self.render_data.enumerate_lookup_indices_in_mut(subtree.clone()).unwrap();
+            for (&i, data) in nodes {
+                data.update_from_state(resource_manager, &state_data[i], opacities[i]);
+            }
+        }
+    }
+
+    /// Same as `update_from_layout`, for the nodes
+    /// of the given subtrees only.
+    pub fn update_from_layout_in(&mut self, layout_data: &LayoutBuffer, subtrees: &[Range<usize>]) {
+
+        for subtree in subtrees {
+            let nodes = self.render_data.enumerate_lookup_indices_in_mut(subtree.clone()).unwrap();
+            for (&i, data) in nodes {
+                data.update_coords(&layout_data[i]);
+            }
+        }
+    }
+
+    pub fn push_commands(&self, list: &mut DisplayList) {
+
+        for data in self.render_data.iter() {
+            data.push_commands(list);
+        }
+    }
+
+    /// Push the commands of the node at the given global index.
+    pub fn push_commands_of(&self, index: usize, list: &mut DisplayList) {
+        if let Some(data) = self.render_data.get_by_global_index(index) {
+            data.push_commands(list);
         }
     }
 }
@@ -93,21 +128,20 @@ mod test {
     use super::RenderBuffer;
     use std::io::BufReader;
     use std::path::PathBuf;
-    use glutin::WindowBuilder;
-    use glium::DisplayBuild;
-    use markup::{self, Node};
+    use markup;
     use style;
     use state::StateBuffer;
     use oil_shared::style::Stylesheet;
     use oil_shared::deps::{Constructor, StyleDefinitions};
     use oil_parsers::{StdOutErrorReporter};
-    use resource::{self, ResourceManager};
+    use resource::{self, BasicResourceManager};
+    use rendering::{DisplayList, DrawCommand};
 
-    fn stylesheet<R: ResourceManager>(st: &str, r: &mut R) -> Stylesheet {
+    fn stylesheet<R: BasicResourceManager>(st: &str, r: &mut R) -> Stylesheet {
         let reader = BufReader::new(st.as_bytes());
         let mut defs = StyleDefinitions::new();
         defs.insert("toto".to_string(),
-            Constructor::Image(PathBuf::new(), None, None, None, None));
+            Constructor::Image(PathBuf::new(), None, None, None, None, None));
         style::parse(StdOutErrorReporter, reader, &defs, r)
     }
 
@@ -118,41 +152,95 @@ mod test {
         root
     }
 
-    // TODO(Nemikolh): Fix that test by allowing glium in headless.
-    //                 Will require some refactoring.
-    //#[test]
-    // fn lookup_table_should_contain_correct_indices() {
-    //
-    //     let mut fake_resource_manager = resource::create_null_manager();
-    //     let stylesheet = stylesheet(
-    //         ".btn { background-image: $toto; }",
-    //         &mut fake_resource_manager);
-    //     let root = markup_tree(
-    //         "<view>\
-    //             <button class=\"btn\"></button>\
-    //             <button class=\"\"></button>\
-    //             <button class=\"btn\"></button>\
-    //         </view>
-    //         ");
-    //     let state_buffer = StateBuffer::new(&root, &stylesheet);
-    //
-    //     // FIXME(This match is here to have the travis test pass)
-    //     if let Some(fake_display) = WindowBuilder::new()
-    //         .with_dimensions(1, 1).with_visibility(false).build_glium().ok()
-    //     {
-    //
-    //         let mut buffer = RenderBuffer::new(
-    //             &fake_display,
-    //             &fake_resource_manager,
-    //             &state_buffer
-    //         );
-    //
-    //         assert_eq!(buffer.render_data.len(), 2);
-    //         let mut iter = buffer.render_data.enumerate_lookup_indices_mut().unwrap();
-    //         let (&i, _) = iter.next().unwrap();
-    //         assert_eq!(i, 1);
-    //         let (&j, _) = iter.next().unwrap();
-    //         assert_eq!(j, 3);
-    //     }
-    // }
+    #[test]
+    fn lookup_table_should_contain_correct_indices() {
+
+        let mut fake_resource_manager = resource::create_null_manager();
+        let stylesheet = stylesheet(
+            ".btn { background-image: $toto; }",
+            &mut fake_resource_manager);
+        let root = markup_tree(
+            "<view>\
+                <button class=\"btn\"></button>\
+                <button class=\"\"></button>\
+                <button class=\"btn\"></button>\
+            </view>
+            ");
+        let state_buffer = StateBuffer::new(&root, &stylesheet);
+
+        let mut buffer = RenderBuffer::new(
+            &fake_resource_manager,
+            &state_buffer
+        );
+
+        assert_eq!(buffer.render_data.len(), 2);
+        let mut iter = buffer.render_data.enumerate_lookup_indices_mut().unwrap();
+        let (&i, _) = iter.next().unwrap();
+        assert_eq!(i, 1);
+        let (&j, _) = iter.next().unwrap();
+        assert_eq!(j, 3);
+    }
+
+    #[test]
+    fn background_color_should_be_drawn_below_the_image() {
+
+        let mut fake_resource_manager = resource::create_null_manager();
+        let stylesheet = stylesheet(
+            ".a { background-color: #ff0000; }\
+             .b { background-color: #00ff00; background-image: $toto; }",
+            &mut fake_resource_manager);
+        let root = markup_tree(
+            "<view>\
+                <button class=\"a\"></button>\
+                <button class=\"b\"></button>\
+            </view>
+            ");
+        let state_buffer = StateBuffer::new(&root, &stylesheet);
+        let buffer = RenderBuffer::new(&fake_resource_manager, &state_buffer);
+
+        let mut list = DisplayList::new();
+        buffer.push_commands(&mut list);
+
+        assert_eq!(list.len(), 3);
+        match list[0] {
+            DrawCommand::Rect { color, .. } => assert_eq!(color, [1.0, 0.0, 0.0, 1.0]),
+            _ => panic!("Expected a rect"),
+        }
+        match list[1] {
+            DrawCommand::Rect { color, .. } => assert_eq!(color, [0.0, 1.0, 0.0, 1.0]),
+            _ => panic!("Expected a rect"),
+        }
+        match list[2] {
+            DrawCommand::Image { .. } => (),
+            _ => panic!("Expected an image"),
+        }
+    }
+
+    #[test]
+    fn opacity_should_be_multiplied_down_the_tree() {
+
+        let mut fake_resource_manager = resource::create_null_manager();
+        let stylesheet = stylesheet(
+            ".a { opacity: 0.5; }\
+             .b { opacity: 0.5; background-color: #000000; }",
+            &mut fake_resource_manager);
+        let root = markup_tree(
+            "<view>\
+                <group class=\"a\">\
+                    <button class=\"b\"></button>\
+                </group>\
+            </view>
+            ");
+        let state_buffer = StateBuffer::new(&root, &stylesheet);
+        let buffer = RenderBuffer::new(&fake_resource_manager, &state_buffer);
+
+        let mut list = DisplayList::new();
+        buffer.push_commands(&mut list);
+
+        assert_eq!(list.len(), 1);
+        match list[0] {
+            DrawCommand::Rect { color, .. } => assert_eq!(color[3], 0.25),
+            _ => panic!("Expected a rect"),
+        }
+    }
 }
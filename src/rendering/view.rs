@@ -1,53 +1,93 @@
-use glium::Display;
 use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
 
-use resource::ResourceManager;
+use resource::{BasicResourceManager, ResourceManager};
 use layout::LayoutBuffer;
 use state::StateBuffer;
 use focus::{FocusBuffer, FocusedElement};
 use super::render::RenderBuffer;
+use super::{DisplayList, DrawCommand};
 use oil_shared::style::SelectorState;
 use oil_shared::style::Stylesheet;
 use data_bindings::DataBindingBuffer;
+use text::TextBuffer;
 use DataBindingsContext;
-use markup;
+use markup::{self, RepeatWindow};
+use util::merge_subtrees;
 use RenderBackbend;
 use Viewport;
 
+/// Items instantiated on each side of the visible
+/// ones by a virtual repeat.
+const VIRTUAL_REPEAT_MARGIN: usize = 2;
+
 pub struct View {
+    name: String,
+    // Set when the whole view must be laid out again, the
+    // nodes that changed are marked in the layout otherwise.
     dirty_flags: bool,
+    // Markup, shared by the views of a library and kept
+    // to instantiate the repeats again
+    markup: Rc<markup::View>,
+    templates: Rc<HashMap<String, markup::Template>>,
+    stylesheet: Rc<Stylesheet>,
+    // Markup with the repeats instantiated
+    instance: markup::View,
     // Buffers
     state_data: StateBuffer,
     focus_data: FocusBuffer,
     layout_data: LayoutBuffer,
     render_data: RenderBuffer,
+    text_data: TextBuffer,
     // Current state
     current_focused_node: FocusedElement,
+    // Set when the focus moves, to scroll it into view.
+    scroll_to_focus: bool,
+    // Instances of each repeat
+    repeat_windows: Vec<RepeatWindow>,
     data_binding_buffer: DataBindingBuffer,
 }
 
 impl View {
 
+    /// Create a new view. `name` is used to find the
+    /// data bindings context of this view.
     pub fn new<R>(
-        display: &Display,
         resource_manager: &R,
-        view: &markup::View,
-        templates: &HashMap<String, markup::Template>,
-        stylesheet: &Stylesheet)
+        name: String,
+        view: Rc<markup::View>,
+        templates: Rc<HashMap<String, markup::Template>>,
+        stylesheet: Rc<Stylesheet>)
         -> View
-        where R: ResourceManager
+        where R: BasicResourceManager
     {
-        let state_buffer = StateBuffer::new(view, stylesheet);
-        let focus_buffer = FocusBuffer::new(view);
-        let layout_buffer = LayoutBuffer::new(view);
-        let render_buffer = RenderBuffer::new(display, resource_manager, &state_buffer);
-        let data_binding_buffer = DataBindingBuffer::new(view, templates);
+        let data_binding_buffer = DataBindingBuffer::new(&view, &templates);
+        let lengths = data_binding_buffer.iterator_lengths();
+        let repeat_windows: Vec<_> = lengths.iter()
+            .map(|&length| RepeatWindow { first: 0, slots: length })
+            .collect();
+        let instance = markup::instantiate_repeats(&view, &templates, &lengths);
+        let state_buffer = StateBuffer::new(&instance, &stylesheet);
+        let focus_buffer = FocusBuffer::new(&instance);
+        let mut layout_buffer = LayoutBuffer::new(&instance);
+        layout_buffer.set_repeat_windows(&lengths, &repeat_windows);
+        let render_buffer = RenderBuffer::new(resource_manager, &state_buffer);
+        let text_buffer = TextBuffer::new(&instance);
 
         View {
+            name: name,
             dirty_flags: true,
+            markup: view,
+            templates: templates,
+            stylesheet: stylesheet,
+            instance: instance,
             layout_data: layout_buffer,
             render_data: render_buffer,
+            text_data: text_buffer,
             current_focused_node: focus_buffer.first_acceptor(),
+            scroll_to_focus: true,
+            repeat_windows: repeat_windows,
             focus_data: focus_buffer,
             state_data: state_buffer,
             data_binding_buffer: data_binding_buffer,
@@ -56,22 +96,149 @@ impl View {
 
     pub fn update<R, C>(
         &mut self,
-        display: &Display,
         resource_manager: &R,
         vp: Viewport,
         context: &mut C)
-        where R: ResourceManager,
+        where R: BasicResourceManager,
               C: DataBindingsContext
     {
-        let updated_bindings = self.data_binding_buffer.update(context, &mut self.layout_data);
-        if self.dirty_flags || updated_bindings {
-            self.set_state_for_focused_node();
-            self.layout_data.update_from_state(&self.state_data);
-            self.layout_data.compute_layout(vp.width, vp.height);
-            self.render_data.update_from_state(display, resource_manager, &self.state_data);
-            self.render_data.update_from_layout(display, &self.layout_data);
-            self.focus_data.update_nodes(&self.layout_data);
-            self.dirty_flags = false;
+        if self.data_binding_buffer.update_iterators(&self.name, context) {
+            let windows = self.repeat_windows.clone();
+            self.instantiate_repeats(resource_manager, &windows);
+        }
+
+        // The windows follow the last layout and its scroll offsets,
+        // the instances recycled by a virtual repeat show other items.
+        if !self.dirty_flags {
+            self.move_repeat_windows(resource_manager);
+        }
+
+        if self.data_binding_buffer.update(&self.name, context) {
+            self.mark_changed_texts();
+        }
+
+        self.relayout(resource_manager, vp);
+    }
+
+    fn relayout<R>(&mut self, resource_manager: &R, vp: Viewport)
+        where R: BasicResourceManager
+    {
+        if self.dirty_flags {
+            self.compute_layout(resource_manager, vp);
+        } else {
+            self.compute_dirty_layout(resource_manager, vp);
+        }
+    }
+
+    /// Copy the data bindings in the text nodes, and
+    /// mark the ones whose text changed as dirty.
+    fn mark_changed_texts(&mut self) {
+        for i in self.text_data.update_from_bindings(&self.data_binding_buffer) {
+            self.layout_data.mark_dirty(i);
+        }
+    }
+
+    fn compute_layout<R>(&mut self, resource_manager: &R, vp: Viewport)
+        where R: BasicResourceManager
+    {
+        self.set_state_for_focused_node();
+        let units = self.state_data.units(vp.width, vp.height);
+        self.text_data.update_from_state(resource_manager, &self.state_data, &units);
+        self.layout_data.update_from_state(&self.state_data);
+        self.layout_data.update_from_text(&self.text_data);
+        self.layout_data.compute_layout(vp.width, vp.height);
+        self.scroll_focused_node_into_view();
+        self.render_data.update_from_state(resource_manager, &self.state_data);
+        self.render_data.update_from_layout(&self.layout_data);
+        self.text_data.update_from_layout(&self.layout_data);
+        self.focus_data.update_nodes(&self.layout_data);
+        self.dirty_flags = false;
+    }
+
+    /// Same as `compute_layout`, for the nodes marked dirty only
+    /// and for the ancestors whose size changed with them.
+    fn compute_dirty_layout<R>(&mut self, resource_manager: &R, vp: Viewport)
+        where R: BasicResourceManager
+    {
+        let dirty = self.layout_data.dirty_subtrees();
+
+        if dirty.is_empty() {
+            return;
+        }
+
+        let units = self.state_data.units(vp.width, vp.height);
+        self.text_data.update_from_state_in(resource_manager, &self.state_data, &units, &dirty);
+        let mut laid_out = self.layout_data.compute_dirty_layout(
+            &self.state_data, &self.text_data, vp.width, vp.height);
+
+        if let Some(container) = self.scroll_focused_node_into_view() {
+            laid_out.push(self.layout_data.subtree(container));
+        }
+
+        self.render_data.update_from_state_in(resource_manager, &self.state_data, &dirty);
+        self.update_from_layout_in(&merge_subtrees(laid_out));
+    }
+
+    /// Copy the dimensions of the given subtrees
+    /// from the layout into the other buffers.
+    fn update_from_layout_in(&mut self, subtrees: &[Range<usize>]) {
+        self.render_data.update_from_layout_in(&self.layout_data, subtrees);
+        self.text_data.update_from_layout_in(&self.layout_data, subtrees);
+        self.focus_data.update_nodes_in(&self.layout_data, subtrees);
+    }
+
+    /// Move the windows of the virtual repeats to the visible items.
+    /// A window needing more instances is instantiated again.
+    /// Must be called after a layout.
+    fn move_repeat_windows<R>(&mut self, resource_manager: &R)
+        where R: BasicResourceManager
+    {
+        let windows = self.layout_data.repeat_windows(
+            &self.repeat_windows, VIRTUAL_REPEAT_MARGIN);
+
+        if windows == self.repeat_windows {
+            return;
+        }
+
+        let grown = windows.iter().zip(self.repeat_windows.iter())
+            .any(|(new, old)| new.slots != old.slots);
+
+        if grown {
+            self.instantiate_repeats(resource_manager, &windows);
+        } else {
+            self.repeat_windows = windows;
+            let lengths = self.data_binding_buffer.iterator_lengths();
+            self.layout_data.set_repeat_windows(&lengths, &self.repeat_windows);
+            self.data_binding_buffer.set_repeat_windows(&self.repeat_windows);
+        }
+    }
+
+    /// Append the draw commands needed to render this view
+    /// to the given display list.
+    pub fn push_commands(&self, list: &mut DisplayList) {
+        let mut clips = self.layout_data.clips().iter().peekable();
+        // End of the clips currently pushed.
+        let mut ends: Vec<usize> = Vec::new();
+
+        for (position, &i) in self.layout_data.paint_order().iter().enumerate() {
+
+            while ends.last().map_or(false, |&end| end <= position) {
+                ends.pop();
+                list.push(DrawCommand::PopClip);
+            }
+
+            while clips.peek().map_or(false, |c| c.start == position) {
+                let clip = clips.next().unwrap();
+                list.push(DrawCommand::PushClip(clip.rect));
+                ends.push(clip.end);
+            }
+
+            self.render_data.push_commands_of(i, list);
+            self.text_data.push_commands_of(i, list);
+        }
+
+        for _ in ends {
+            list.push(DrawCommand::PopClip);
         }
     }
 
@@ -81,43 +248,143 @@ impl View {
         resource_manager: &R,
         frame: &mut <B as RenderBackbend>::Frame)
         where B: RenderBackbend,
-              R: ResourceManager
+              R: ResourceManager<Texture=B::Texture>
     {
-        for data in self.render_data.iter() {
-            backend.render_element(resource_manager, frame, data);
-        }
+        let mut list = DisplayList::new();
+        self.push_commands(&mut list);
+        backend.render_display_list(resource_manager, frame, &list);
     }
 
     pub fn focus_up(&mut self) {
         if let Some(new_focused_node) = self.focus_data.focus_up(&self.current_focused_node) {
-            self.remove_state_for_focused_node();
-            self.current_focused_node = new_focused_node;
-            self.dirty_flags = true;
+            self.set_focused_node(new_focused_node);
         }
     }
 
     pub fn focus_down(&mut self) {
         if let Some(new_focused_node) = self.focus_data.focus_down(&self.current_focused_node) {
-            self.remove_state_for_focused_node();
-            self.current_focused_node = new_focused_node;
-            self.dirty_flags = true;
+            self.set_focused_node(new_focused_node);
         }
     }
 
     pub fn focus_right(&mut self) {
         if let Some(new_focused_node) = self.focus_data.focus_right(&self.current_focused_node) {
-            self.remove_state_for_focused_node();
-            self.current_focused_node = new_focused_node;
-            self.dirty_flags = true;
+            self.set_focused_node(new_focused_node);
         }
     }
 
     pub fn focus_left(&mut self) {
         if let Some(new_focused_node) = self.focus_data.focus_left(&self.current_focused_node) {
-            self.remove_state_for_focused_node();
-            self.current_focused_node = new_focused_node;
-            self.dirty_flags = true;
+            self.set_focused_node(new_focused_node);
+        }
+    }
+
+    /// Scroll the container of the focused node, or the first
+    /// container with `overflow: scroll` if there is none.
+    /// Meant for the mouse wheel or a gamepad stick, `dx`
+    /// and `dy` are in pixels.
+    pub fn scroll(&mut self, dx: f32, dy: f32) {
+        let focused = self.focus_data.global_index(&self.current_focused_node);
+
+        if let Some(container) = self.layout_data.scroll_container(focused) {
+            // Only the content moves, nothing is laid out again.
+            if self.layout_data.scroll_by(container, dx, dy) {
+                let subtree = self.layout_data.subtree(container);
+                self.update_from_layout_in(&[subtree]);
+            }
+        }
+    }
+
+    /// Returns the global index of the outermost node that scrolled.
+    fn scroll_focused_node_into_view(&mut self) -> Option<usize> {
+
+        let mut scrolled = None;
+
+        if self.scroll_to_focus {
+            if let Some(global_index) = self.focus_data.global_index(&self.current_focused_node) {
+                scrolled = self.layout_data.scroll_into_view(global_index);
+            }
+            self.scroll_to_focus = false;
+        }
+
+        scrolled
+    }
+
+    /// Rebuild all the buffers from the markup, with the current
+    /// number of items of each repeat. A virtual repeat gets the
+    /// given window, as long as it fits in the items.
+    /// The focused node keeps the focus, and the scroll containers
+    /// their offset, if they are still instantiated.
+    fn instantiate_repeats<R>(&mut self, resource_manager: &R, windows: &[RepeatWindow])
+        where R: BasicResourceManager
+    {
+        let lengths = self.data_binding_buffer.iterator_lengths();
+        let virtuals = markup::virtual_repeats(&self.markup);
+        let old_origins = markup::node_origins(&self.instance, &self.repeat_windows);
+        let focused = self.focus_data.global_index(&self.current_focused_node)
+            .and_then(|i| old_origins.get(i).cloned());
+        let scrolls: Vec<_> = self.layout_data.scroll_offsets().into_iter()
+            .filter_map(|(i, scroll)| old_origins.get(i).map(|&origin| (origin, scroll)))
+            .collect();
+
+        self.repeat_windows = lengths.iter().zip(virtuals.iter()).enumerate()
+            .map(|(i, (&length, &is_virtual))| {
+                let window = windows.get(i).map_or(RepeatWindow::default(), |&w| w);
+                if is_virtual {
+                    let slots = window.slots.min(length);
+                    RepeatWindow {
+                        first: window.first.min(length - slots),
+                        slots: slots,
+                    }
+                } else {
+                    RepeatWindow { first: 0, slots: length }
+                }
+            })
+            .collect();
+
+        let slots: Vec<_> = self.repeat_windows.iter().map(|w| w.slots).collect();
+        self.instance = markup::instantiate_repeats(&self.markup, &self.templates, &slots);
+
+        self.state_data = StateBuffer::new(&self.instance, &self.stylesheet);
+        self.focus_data = FocusBuffer::new(&self.instance);
+        self.layout_data = LayoutBuffer::new(&self.instance);
+        self.layout_data.set_repeat_windows(&lengths, &self.repeat_windows);
+        self.render_data = RenderBuffer::new(resource_manager, &self.state_data);
+        self.text_data = TextBuffer::new(&self.instance);
+        self.data_binding_buffer.set_instance(&self.instance);
+        self.data_binding_buffer.set_repeat_windows(&self.repeat_windows);
+
+        let origins = markup::node_origins(&self.instance, &self.repeat_windows);
+
+        // The next layout scrolls the containers back to their offset.
+        for &(origin, scroll) in scrolls.iter() {
+            if let Some(i) = origins.iter().position(|&o| o == origin) {
+                self.layout_data.set_scroll_offset(i, scroll);
+            }
+        }
+
+        let refocused = focused
+            .and_then(|origin| origins.iter().position(|&o| o == origin))
+            .and_then(|i| self.focus_data.acceptor_at(i));
+
+        match refocused {
+            Some(node) => self.current_focused_node = node,
+            // The previously focused node doesn't exist anymore.
+            None => {
+                self.current_focused_node = self.focus_data.first_acceptor();
+                self.scroll_to_focus = true;
+            }
         }
+        self.dirty_flags = true;
+    }
+
+    /// Move the focus. Only the nodes losing and
+    /// getting the focus will be laid out again.
+    fn set_focused_node(&mut self, node: FocusedElement) {
+        self.remove_state_for_focused_node();
+        self.current_focused_node = node;
+        self.set_state_for_focused_node();
+        self.scroll_to_focus = true;
     }
 
     fn set_state_for_focused_node(&mut self) {
@@ -127,6 +394,7 @@ impl View {
             self.state_data.get_mut(global_index)
                 .unwrap()
                 .set_current_state(SelectorState::Focus);
+            self.layout_data.mark_dirty(global_index);
         }
     }
 
@@ -137,6 +405,7 @@ impl View {
             self.state_data.get_mut(global_index)
                 .unwrap()
                 .set_current_state(SelectorState::Default);
+            self.layout_data.mark_dirty(global_index);
         }
     }
 }
@@ -145,3 +414,96 @@ impl View {
 // ======================================== //
 //                   TESTS                  //
 // ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::io::BufReader;
+    use std::rc::Rc;
+    use super::View;
+    use markup;
+    use style;
+    use layout::Rect;
+    use resource;
+    use data_bindings::DefaultContextManager;
+    use oil_shared::deps::StyleDefinitions;
+    use oil_parsers::StdOutErrorReporter;
+    use Viewport;
+
+    /// Bounds of each node, lines of each text, line number of each
+    /// focus node, and the nodes reached from each focus acceptor
+    /// going up, down, left and right.
+    fn snapshot(view: &View)
+        -> (Vec<(Rect, Rect)>, Vec<Vec<(String, Rect)>>, Vec<usize>, Vec<Vec<Option<usize>>>)
+    {
+        let layout = view.layout_data.iter()
+            .map(|lb| (lb.dim().content, lb.dim().margin_box()))
+            .collect();
+        let texts = view.text_data.iter().map(|t| t.lines().to_vec()).collect();
+        let lines = view.focus_data.iter().map(|f| f.line_number()).collect();
+        let focus = &view.focus_data;
+        let navigation = (0..view.layout_data.len())
+            .filter_map(|i| focus.acceptor_at(i))
+            .map(|el| {
+                let next = [
+                    focus.focus_up(&el),
+                    focus.focus_down(&el),
+                    focus.focus_left(&el),
+                    focus.focus_right(&el),
+                ];
+                next.iter().map(|n| n.as_ref().and_then(|n| focus.global_index(n))).collect()
+            })
+            .collect();
+        (layout, texts, lines, navigation)
+    }
+
+    #[test]
+    fn dirty_layout_should_match_a_full_layout() {
+        let mut fake_resource_manager = resource::create_null_manager();
+        let defs = StyleDefinitions::new();
+        let stylesheet = style::parse(
+            StdOutErrorReporter,
+            BufReader::new(".screen { width: 100vw; height: 100vh; }\
+                            .panel { width: 400px; height: 200px; }\
+                            .box { width: 400px; height: 30px; }\
+                            .box:focus { height: 60px; }".as_bytes()),
+            &defs,
+            &mut fake_resource_manager);
+        let lib = markup::parse(StdOutErrorReporter, BufReader::new(
+            "<view>\
+                <group class=\"screen\">\
+                    <group class=\"panel\">\
+                        <button class=\"box\">One</button>\
+                        <button class=\"box\">Two</button>\
+                    </group>\
+                    <group class=\"panel\">\
+                        <button class=\"box\">Three</button>\
+                        <button class=\"box\">Four</button>\
+                    </group>\
+                </group>\
+            </view>".as_bytes()));
+        let (name, view) = lib.views.into_iter().next().unwrap();
+        let mut view = View::new(
+            &fake_resource_manager,
+            name,
+            Rc::new(view),
+            Rc::new(lib.templates),
+            Rc::new(stylesheet));
+        let vp = Viewport { width: 800.0, height: 600.0 };
+        let mut context = DefaultContextManager::default();
+
+        view.update(&fake_resource_manager, vp, &mut context);
+        assert!(!view.dirty_flags);
+
+        // Only the first panel is laid out again.
+        view.focus_down();
+        view.update(&fake_resource_manager, vp, &mut context);
+        assert_eq!(view.focus_data.global_index(&view.current_focused_node), Some(5));
+        assert_eq!(view.layout_data[5].dim().content.height, 60.0);
+        let dirty = snapshot(&view);
+
+        view.dirty_flags = true;
+        view.update(&fake_resource_manager, vp, &mut context);
+        assert_eq!(dirty, snapshot(&view));
+    }
+}
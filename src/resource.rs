@@ -1,7 +1,5 @@
 use std::path::Path;
-use glium::{self, Display};
-use glium::texture::CompressedTexture2d;
-use image::{self, GenericImage};
+use image::{self, RgbaImage};
 use oil_shared::resource::new_resource_id;
 
 
@@ -9,18 +7,36 @@ use oil_shared::resource::new_resource_id;
 
 pub use oil_shared::resource::ResourceId;
 pub use oil_shared::resource::BasicResourceManager;
+pub use text::{Font, GlyphQuad};
 
 // ======================================== //
 //                INTERFACE                 //
 // ======================================== //
 
+/// A `ResourceManager` gives access to the textures
+/// loaded by the `BasicResourceManager` in a form that
+/// a `RenderBackbend` understands.
 pub trait ResourceManager: BasicResourceManager {
 
-    fn get_texture(&self, id: ResourceId) -> &CompressedTexture2d;
+    /// Texture type used by the backend.
+    type Texture;
+
+    fn get_texture(&self, id: ResourceId) -> &Self::Texture;
+
+    /// Font loaded with `get_font_id`.
+    fn get_font(&self, id: ResourceId) -> &Font;
+
+    /// Texture holding the glyph atlas of the font `id`.
+    fn get_font_atlas(&self, id: ResourceId) -> &Self::Texture;
 }
 
-pub fn create_resource_manager<'a>(display: &'a Display) -> ResourceManagerImpl {
-    ResourceManagerImpl::new(display)
+#[cfg(feature = "glium")]
+pub use self::glium_impl::{create_resource_manager, ResourceManagerImpl};
+
+/// Create a ResourceManager that keeps the images in memory.
+/// This is the resource manager expected by the `SoftwareRenderer`.
+pub fn create_software_manager() -> SoftwareResourceManager {
+    SoftwareResourceManager::new()
 }
 
 /// Create a ResourceManager that does nothing.
@@ -34,61 +50,72 @@ pub fn create_null_manager() -> NullResourceManager {
 //                INTERNALS                 //
 // ======================================== //
 
-struct TextureResource {
-    handle: glium::texture::CompressedTexture2d,
-    img_width: u32,
-    img_height: u32,
+pub struct SoftwareResourceManager {
+    images: Vec<RgbaImage>,
+    // Fonts along with the id of their atlas.
+    fonts: Vec<(Font, ResourceId)>,
 }
 
-pub struct ResourceManagerImpl<'a> {
-    textures: Vec<TextureResource>,
-    display: &'a Display
-}
+impl SoftwareResourceManager {
 
-impl<'a> ResourceManagerImpl<'a> {
-
-    fn new(display: &'a Display) -> ResourceManagerImpl<'a> {
-        ResourceManagerImpl {
-            textures: Vec::new(),
-            display: display,
+    fn new() -> SoftwareResourceManager {
+        SoftwareResourceManager {
+            images: Vec::new(),
+            fonts: Vec::new(),
         }
     }
+
+    /// Register an image that hasn't been loaded from a file.
+    pub fn insert_image(&mut self, image: RgbaImage) -> ResourceId {
+        let id = self.images.len();
+        self.images.push(image);
+        unsafe { new_resource_id(id) }
+    }
 }
 
-impl<'a> BasicResourceManager for ResourceManagerImpl<'a> {
+impl BasicResourceManager for SoftwareResourceManager {
 
     fn get_texture_id(&mut self, p: &Path)
         -> ResourceId
     {
-        let image = image::open(p).unwrap();
-        let (iw, ih) = image.dimensions();
-        let tex = glium::texture::CompressedTexture2d::new(self.display, image);
-        let id = self.textures.len();
-        self.textures.push(TextureResource {
-            handle: tex,
-            img_width: iw,
-            img_height: ih
-        });
-        unsafe { new_resource_id(id) }
+        let image = image::open(p).unwrap().to_rgba();
+        self.insert_image(image)
+    }
+
+    fn get_image_dimensions(&self, id: ResourceId) -> (u32, u32) {
+        unsafe { self.images[id.get()].dimensions() }
     }
 
-    fn get_image_dimensions(
-        &self,
-        id: ResourceId)
-        -> (u32, u32)
+    fn get_font_id(&mut self, p: &Path, width: f32, height: f32)
+        -> Option<ResourceId>
     {
-        unsafe {
-            (self.textures[id.get()].img_width, self.textures[id.get()].img_height)
-        }
+        Font::from_file(p, width, height).ok().map(|font| {
+            let atlas = self.insert_image(font.atlas().clone());
+            let id = self.fonts.len();
+            self.fonts.push((font, atlas));
+            unsafe { new_resource_id(id) }
+        })
+    }
+
+    fn measure_text(&self, id: ResourceId, text: &str) -> (f32, f32) {
+        self.get_font(id).measure(text)
     }
 }
 
-impl<'a> ResourceManager for ResourceManagerImpl<'a> {
+impl ResourceManager for SoftwareResourceManager {
 
-    fn get_texture(&self, id: ResourceId)
-        -> &CompressedTexture2d
-    {
-        unsafe { &self.textures[id.get()].handle }
+    type Texture = RgbaImage;
+
+    fn get_texture(&self, id: ResourceId) -> &RgbaImage {
+        unsafe { &self.images[id.get()] }
+    }
+
+    fn get_font(&self, id: ResourceId) -> &Font {
+        unsafe { &self.fonts[id.get()].0 }
+    }
+
+    fn get_font_atlas(&self, id: ResourceId) -> &RgbaImage {
+        unsafe { self.get_texture(self.fonts[id.get()].1) }
     }
 }
 
@@ -105,14 +132,145 @@ impl BasicResourceManager for NullResourceManager {
     fn get_image_dimensions(&self, _: ResourceId) -> (u32, u32) {
         (0, 0)
     }
+
+    // No font is ever loaded.
+    fn get_font_id(&mut self, _: &Path, _: f32, _: f32)
+        -> Option<ResourceId>
+    {
+        None
+    }
+
+    fn measure_text(&self, _: ResourceId, _: &str) -> (f32, f32) {
+        (0.0, 0.0)
+    }
 }
 
 impl ResourceManager for NullResourceManager {
 
+    type Texture = RgbaImage;
+
     fn get_texture(&self, _: ResourceId)
-        -> &CompressedTexture2d
+        -> &RgbaImage
     {
         panic!("NullResourceManager purpose is for test only,\
                 it has a limited use.");
     }
+
+    fn get_font(&self, _: ResourceId) -> &Font {
+        panic!("NullResourceManager purpose is for test only,\
+                it has a limited use.");
+    }
+
+    fn get_font_atlas(&self, _: ResourceId) -> &RgbaImage {
+        panic!("NullResourceManager purpose is for test only,\
+                it has a limited use.");
+    }
+}
+
+// Resource manager used by the glium backend.
+#[cfg(feature = "glium")]
+mod glium_impl {
+
+    use std::path::Path;
+    use glium::{self, Display};
+    use glium::texture::CompressedTexture2d;
+    use image::{self, GenericImage, DynamicImage};
+    use oil_shared::resource::new_resource_id;
+    use super::{BasicResourceManager, ResourceManager, ResourceId, Font};
+
+    pub fn create_resource_manager<'a>(display: &'a Display) -> ResourceManagerImpl {
+        ResourceManagerImpl::new(display)
+    }
+
+    struct TextureResource {
+        handle: glium::texture::CompressedTexture2d,
+        img_width: u32,
+        img_height: u32,
+    }
+
+    pub struct ResourceManagerImpl<'a> {
+        textures: Vec<TextureResource>,
+        // Fonts along with the id of their atlas.
+        fonts: Vec<(Font, ResourceId)>,
+        display: &'a Display
+    }
+
+    impl<'a> ResourceManagerImpl<'a> {
+
+        fn new(display: &'a Display) -> ResourceManagerImpl<'a> {
+            ResourceManagerImpl {
+                textures: Vec::new(),
+                fonts: Vec::new(),
+                display: display,
+            }
+        }
+
+        fn insert_texture(&mut self, image: DynamicImage) -> ResourceId {
+            let (iw, ih) = image.dimensions();
+            let tex = glium::texture::CompressedTexture2d::new(self.display, image);
+            let id = self.textures.len();
+            self.textures.push(TextureResource {
+                handle: tex,
+                img_width: iw,
+                img_height: ih
+            });
+            unsafe { new_resource_id(id) }
+        }
+    }
+
+    impl<'a> BasicResourceManager for ResourceManagerImpl<'a> {
+
+        fn get_texture_id(&mut self, p: &Path)
+            -> ResourceId
+        {
+            let image = image::open(p).unwrap();
+            self.insert_texture(image)
+        }
+
+        fn get_font_id(&mut self, p: &Path, width: f32, height: f32)
+            -> Option<ResourceId>
+        {
+            Font::from_file(p, width, height).ok().map(|font| {
+                let atlas = self.insert_texture(
+                    DynamicImage::ImageRgba8(font.atlas().clone())
+                );
+                let id = self.fonts.len();
+                self.fonts.push((font, atlas));
+                unsafe { new_resource_id(id) }
+            })
+        }
+
+        fn measure_text(&self, id: ResourceId, text: &str) -> (f32, f32) {
+            self.get_font(id).measure(text)
+        }
+
+        fn get_image_dimensions(
+            &self,
+            id: ResourceId)
+            -> (u32, u32)
+        {
+            unsafe {
+                (self.textures[id.get()].img_width, self.textures[id.get()].img_height)
+            }
+        }
+    }
+
+    impl<'a> ResourceManager for ResourceManagerImpl<'a> {
+
+        type Texture = CompressedTexture2d;
+
+        fn get_texture(&self, id: ResourceId)
+            -> &CompressedTexture2d
+        {
+            unsafe { &self.textures[id.get()].handle }
+        }
+
+        fn get_font(&self, id: ResourceId) -> &Font {
+            unsafe { &self.fonts[id.get()].0 }
+        }
+
+        fn get_font_atlas(&self, id: ResourceId) -> &CompressedTexture2d {
+            unsafe { self.get_texture(self.fonts[id.get()].1) }
+        }
+    }
 }
@@ -2,11 +2,11 @@
 use std::collections::hash_map::{HashMap,Keys};
 use std::rc::Rc;
 use std::cell::RefCell;
-use glium::Display;
 
 use markup::MAIN_VIEW_NAME;
 use markup::Library;
-use resource::ResourceManager;
+use resource::{BasicResourceManager, ResourceManager};
+use rendering::DisplayList;
 use oil_shared::style::Stylesheet;
 use DataBindingsContext;
 use RenderBackbend;
@@ -28,16 +28,20 @@ impl Router {
     }
 
     pub fn from_library_and_stylesheet<R, E>(
-        display: &Display,
         resource_manager:  &R,
         lib: Library<E>,
         style: &Stylesheet)
         -> Router
-        where R: ResourceManager
+        where R: BasicResourceManager
     {
         let mut router = Router::new();
+        // The views share the templates and the stylesheet.
+        let templates = Rc::new(lib.templates);
+        let style = Rc::new(style.clone());
         for (name, view) in lib.views.into_iter() {
-            router.add_view(name, View::new(display, resource_manager, &view, &lib.templates, style));
+            let v = View::new(resource_manager, name.clone(), Rc::new(view),
+                templates.clone(), style.clone());
+            router.add_view(name, v);
         }
         router
     }
@@ -87,17 +91,23 @@ impl Router {
         }
     }
 
+    /// Scroll the view on top of the stack, see `View::scroll`.
+    pub fn scroll(&mut self, dx: f32, dy: f32) {
+        if let Some(&mut (_, ref mut view)) = self.stack.last_mut() {
+            view.borrow_mut().scroll(dx, dy);
+        }
+    }
+
     pub fn update<R, C>(
         &mut self,
-        display: &Display,
         resource_manager: &R,
         vp: Viewport,
         context: &mut C)
-        where R: ResourceManager,
+        where R: BasicResourceManager,
               C: DataBindingsContext
     {
         for &mut (_, ref mut v) in self.stack.iter_mut() {
-            v.borrow_mut().update(display, resource_manager, vp, context);
+            v.borrow_mut().update(resource_manager, vp, context);
         }
     }
 
@@ -110,16 +120,24 @@ impl Router {
         self.views.insert(name_str, rcv);
     }
 
+    /// Returns the draw commands for all the views in the stack.
+    /// Views are drawn from the bottom of the stack to the top.
+    pub fn display_list(&self) -> DisplayList {
+        let mut list = DisplayList::new();
+        for &(_, ref v) in &self.stack {
+            v.borrow().push_commands(&mut list);
+        }
+        list
+    }
+
     pub fn render_views<R, C>(
         &self,
         ctx: &C,
         frame: &mut C::Frame,
         resource_manager: &R)
         where C: RenderBackbend,
-              R: ResourceManager
+              R: ResourceManager<Texture=C::Texture>
     {
-        for &(_, ref v) in &self.stack {
-            v.borrow().render(ctx, resource_manager, frame);
-        }
+        ctx.render_display_list(resource_manager, frame, &self.display_list());
     }
 }
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
-use util::BufferFromTree;
-use oil_shared::asset::ImageData;
+use util::{BufferFromTree, parent_indices};
+use layout::{Length, Units, Flex, FlexDirection, AlignItems, JustifyContent, Grid, Track};
+use oil_shared::asset::{ImageData, FontData};
+use oil_shared::color::Color;
 use oil_shared::properties::PropertyName;
 use oil_shared::properties::STYLE_PROPERTIES;
 use oil_shared::style::Value;
@@ -13,10 +15,13 @@ use oil_shared::style::Unit;
 use oil_shared::style::Rule;
 use oil_shared::markup::Node;
 use rendering::TextureRule;
+use text::{TextAlign, WhiteSpace};
 
 
 pub struct StateBuffer {
     state_data: BufferFromTree<StateData>,
+    // Global index of the parent of each node
+    parents: Vec<Option<usize>>,
 }
 
 impl Deref for StateBuffer {
@@ -45,9 +50,32 @@ impl StateBuffer {
         };
 
         StateBuffer {
-            state_data: BufferFromTree::new(tree, size, converter)
+            state_data: BufferFromTree::new(tree, size, converter),
+            parents: parent_indices(tree),
         }
     }
+
+    /// Units to resolve the lengths of this view with the given
+    /// viewport, the `root-unit` is read on the root node.
+    pub fn units(&self, viewport_width: f32, viewport_height: f32) -> Units {
+        let root_unit = self.state_data.iter().next()
+            .and_then(|root| root.length_prop_as_opt(PropertyName::ROOT_UNIT));
+        Units::new(viewport_width, viewport_height, root_unit)
+    }
+
+    /// Returns the opacity of each node in the current state,
+    /// multiplied by the opacity of its ancestors.
+    pub fn opacities(&self) -> Vec<f32> {
+        let mut opacities: Vec<f32> = Vec::with_capacity(self.parents.len());
+
+        for (state, parent) in self.state_data.iter().zip(self.parents.iter()) {
+            // Parents always come before their children.
+            let inherited = parent.map_or(1f32, |p| opacities[p]);
+            opacities.push(inherited * state.get_opacity());
+        }
+
+        opacities
+    }
 }
 
 pub struct StateData {
@@ -68,7 +96,7 @@ macro_rules! return_length_or_zero {
 
     ($this:ident try $prop_name:ident) => {
         return_length_or_zero!(rec $this try $prop_name else {
-            return_length_or_zero!(rec_default $this try $prop_name else { 0f32 })
+            return_length_or_zero!(rec_default $this try $prop_name else { Length::default() })
         });
     };
 
@@ -76,7 +104,7 @@ macro_rules! return_length_or_zero {
         return_length_or_zero!(rec $this try $prop_name else {
             return_length_or_zero!(rec $this try $other else {
                 return_length_or_zero!(rec_default $this try $prop_name else {
-                    return_length_or_zero!(rec_default $this try $other else { 0f32 })
+                    return_length_or_zero!(rec_default $this try $other else { Length::default() })
                 })
             })
         });
@@ -85,10 +113,10 @@ macro_rules! return_length_or_zero {
     (rec $this:ident try $prop_name:ident else $none_case:block) => {
         match $this.current_properties().get(&$prop_name) {
             Some(v) => {
-                if let Value::Length(val, Unit::Px) = *v {
-                    val
+                if let Value::Length(val, unit) = *v {
+                    Length { value: val, unit: unit }
                 } else {
-                    0f32
+                    Length::default()
                 }
             }
             None => $none_case
@@ -98,10 +126,10 @@ macro_rules! return_length_or_zero {
     (rec_default $this:ident try $prop_name:ident else $none_case:block) => {
         match $this.default_properties.get(&$prop_name) {
             Some(v) => {
-                if let Value::Length(val, Unit::Px) = *v {
-                    val
+                if let Value::Length(val, unit) = *v {
+                    Length { value: val, unit: unit }
                 } else {
-                    0f32
+                    Length::default()
                 }
             }
             None => $none_case
@@ -157,12 +185,14 @@ impl StateData {
             Some(&Value::Keyword(v)) => match v {
                 KwValue::Fit => Some(TextureRule::Fit),
                 KwValue::Repeat => Some(TextureRule::Repeat),
+                KwValue::Slice => Some(TextureRule::Slice),
                 _ => None
             },
             _ => match self.default_properties.get(&PropertyName::BACKGROUND_IMAGE_RULE) {
                 Some(&Value::Keyword(v)) => match v {
                     KwValue::Fit => Some(TextureRule::Fit),
                     KwValue::Repeat => Some(TextureRule::Repeat),
+                    KwValue::Slice => Some(TextureRule::Slice),
                     _ => None
                 },
                 _ => None
@@ -171,15 +201,182 @@ impl StateData {
     }
 
     pub fn get_background_image(&self) -> Option<ImageData> {
-        match self.current_properties().get(&PropertyName::BACKGROUND_IMAGE) {
+        self.get_image_prop(PropertyName::BACKGROUND_IMAGE)
+    }
+
+    pub fn get_border_image(&self) -> Option<ImageData> {
+        self.get_image_prop(PropertyName::BORDER_IMAGE)
+    }
+
+    pub fn get_border_color(&self) -> Option<Color> {
+        self.get_color_prop(PropertyName::BORDER_COLOR)
+    }
+
+    /// In pixels or in percentage of the image size.
+    pub fn get_background_slice(&self) -> Option<Length> {
+        self.length_prop_as_opt(PropertyName::BACKGROUND_SLICE)
+    }
+
+    pub fn get_background_color(&self) -> Option<Color> {
+        self.get_color_prop(PropertyName::BACKGROUND_COLOR)
+    }
+
+    pub fn get_color(&self) -> Option<Color> {
+        self.get_color_prop(PropertyName::COLOR)
+    }
+
+    /// Returns true if a background or a border
+    /// has to be drawn in any state.
+    pub fn has_decorations(&self) -> bool {
+        let maps = [
+            &self.default_properties,
+            &self.focus_properties,
+            &self.hover_properties,
+            &self.creation_properties,
+        ];
+        maps.iter().any(|m| {
+            m.contains_key(&PropertyName::BACKGROUND_IMAGE)
+            || m.contains_key(&PropertyName::BACKGROUND_COLOR)
+            || m.contains_key(&PropertyName::BORDER_COLOR)
+            || m.contains_key(&PropertyName::BORDER_IMAGE)
+        })
+    }
+
+    /// Opacity of the node alone, between 0 and 1.
+    pub fn get_opacity(&self) -> f32 {
+        self.size_prop_as_opt(PropertyName::OPACITY)
+            .map_or(1f32, |o| o.max(0.0).min(1.0))
+    }
+
+    pub fn get_font(&self) -> Option<FontData> {
+        match self.current_properties().get(&PropertyName::FONT) {
+            Some(&Value::Font(ref font)) => Some(font.clone()),
+            _ => match self.default_properties.get(&PropertyName::FONT) {
+                Some(&Value::Font(ref font)) => Some(font.clone()),
+                _ => None
+            }
+        }
+    }
+
+    pub fn get_text_align(&self) -> Option<TextAlign> {
+        match self.get_keyword(PropertyName::TEXT_ALIGN) {
+            Some(KwValue::Left) => Some(TextAlign::Left),
+            Some(KwValue::Center) => Some(TextAlign::Center),
+            Some(KwValue::Right) => Some(TextAlign::Right),
+            _ => None
+        }
+    }
+
+    pub fn get_white_space(&self) -> Option<WhiteSpace> {
+        match self.get_keyword(PropertyName::WHITE_SPACE) {
+            Some(KwValue::Normal) => Some(WhiteSpace::Normal),
+            Some(KwValue::NoWrap) => Some(WhiteSpace::NoWrap),
+            Some(KwValue::Pre) => Some(WhiteSpace::Pre),
+            Some(KwValue::PreLine) => Some(WhiteSpace::PreLine),
+            _ => None
+        }
+    }
+
+    /// Returns the stack layout of this node
+    /// if its `layout` is `row` or `column`.
+    pub fn get_flex(&self) -> Option<Flex> {
+        let direction = match self.get_keyword(PropertyName::LAYOUT_MODE) {
+            Some(KwValue::Row) => FlexDirection::Row,
+            Some(KwValue::Column) => FlexDirection::Column,
+            _ => return None
+        };
+        let align_items = match self.get_keyword(PropertyName::ALIGN_ITEMS) {
+            Some(KwValue::Start) => AlignItems::Start,
+            Some(KwValue::Center) => AlignItems::Center,
+            Some(KwValue::End) => AlignItems::End,
+            Some(KwValue::Stretch) => AlignItems::Stretch,
+            _ => AlignItems::default()
+        };
+        let justify_content = match self.get_keyword(PropertyName::JUSTIFY_CONTENT) {
+            Some(KwValue::Start) => JustifyContent::Start,
+            Some(KwValue::Center) => JustifyContent::Center,
+            Some(KwValue::End) => JustifyContent::End,
+            Some(KwValue::SpaceBetween) => JustifyContent::SpaceBetween,
+            Some(KwValue::SpaceAround) => JustifyContent::SpaceAround,
+            Some(KwValue::SpaceEvenly) => JustifyContent::SpaceEvenly,
+            _ => JustifyContent::default()
+        };
+
+        Some(Flex {
+            direction: direction,
+            align_items: align_items,
+            justify_content: justify_content,
+            gap: self.length_prop_as_opt(PropertyName::GAP).unwrap_or(Length::default()),
+        })
+    }
+
+    /// Returns the tracks of this node if its `layout` is `grid`.
+    pub fn get_grid(&self) -> Option<Grid> {
+        if self.get_keyword(PropertyName::LAYOUT_MODE) != Some(KwValue::Grid) {
+            return None;
+        }
+
+        Some(Grid {
+            columns: self.get_tracks(PropertyName::GRID_COLUMNS),
+            rows: self.get_tracks(PropertyName::GRID_ROWS),
+            gap: self.length_prop_as_opt(PropertyName::GAP).unwrap_or(Length::default()),
+        })
+    }
+
+    fn get_tracks(&self, prop_name: PropertyName) -> Vec<Track> {
+        fn to_track(value: &Value) -> Option<Track> {
+            match *value {
+                Value::Length(val, Unit::Fr) => Some(Track::Fraction(val)),
+                Value::Length(val, unit) => Some(Track::Fixed(Length { value: val, unit: unit })),
+                Value::Keyword(KwValue::Auto) => Some(Track::Auto),
+                _ => None
+            }
+        }
+
+        let value = match self.current_properties().get(&prop_name) {
+            Some(v) => Some(v),
+            None => self.default_properties.get(&prop_name),
+        };
+
+        match value {
+            Some(&Value::List(ref values)) => values.iter().filter_map(to_track).collect(),
+            Some(v) => to_track(v).into_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn get_image_prop(&self, prop_name: PropertyName) -> Option<ImageData> {
+        match self.current_properties().get(&prop_name) {
             Some(&Value::Image(ref id)) => Some(id.clone()),
-            _ => match self.default_properties.get(&PropertyName::BACKGROUND_IMAGE) {
+            _ => match self.default_properties.get(&prop_name) {
                 Some(&Value::Image(ref id)) => Some(id.clone()),
                 _ => None
             }
         }
     }
 
+    fn get_color_prop(&self, prop_name: PropertyName) -> Option<Color> {
+        match self.current_properties().get(&prop_name) {
+            Some(&Value::Color(c)) => Some(c),
+            _ => match self.default_properties.get(&prop_name) {
+                Some(&Value::Color(c)) => Some(c),
+                _ => None
+            }
+        }
+    }
+
+    fn get_keyword(&self, prop_name: PropertyName) -> Option<KwValue> {
+        match self.current_properties().get(&prop_name) {
+            Some(&Value::Keyword(v)) => Some(v),
+            _ => match self.default_properties.get(&prop_name) {
+                Some(&Value::Keyword(v)) => Some(v),
+                _ => None
+            }
+        }
+    }
+
+    /// Value of a plain number property (`opacity`, `z-index`...),
+    /// the style parser rejects any unit other than `px` for them.
     pub fn size_prop_as_opt(&self, prop_name: PropertyName) -> Option<f32> {
         match self.current_properties().get(&prop_name) {
             Some(v) => {
@@ -202,7 +399,20 @@ impl StateData {
         }
     }
 
-    pub fn size_of_prop(&self, prop_name: PropertyName) -> f32 {
+    /// Same as `size_prop_as_opt` but accepts any unit.
+    /// The length is resolved by the layout.
+    pub fn length_prop_as_opt(&self, prop_name: PropertyName) -> Option<Length> {
+        match self.current_properties().get(&prop_name) {
+            Some(&Value::Length(val, unit)) => Some(Length { value: val, unit: unit }),
+            Some(_) => None,
+            None => match self.default_properties.get(&prop_name) {
+                Some(&Value::Length(val, unit)) => Some(Length { value: val, unit: unit }),
+                _ => None
+            }
+        }
+    }
+
+    pub fn length_of_prop(&self, prop_name: PropertyName) -> Length {
         use oil_shared::properties::PropertyName::MARGIN;
         use oil_shared::properties::PropertyName::PADDING;
         use oil_shared::properties::PropertyName::BORDER;
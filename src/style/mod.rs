@@ -1,2 +1,2 @@
 
-pub use oil_parsers::style::parse;
+pub use oil_parsers::style::{parse, parse_file};
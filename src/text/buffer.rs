@@ -0,0 +1,241 @@
+use std::cell::Cell;
+use std::iter::Zip;
+use std::ops::{Deref, Range};
+use std::slice::Iter;
+
+use markup::{Node, NodeType};
+use layout::{LayoutBuffer, Units};
+use data_bindings::DataBindingBuffer;
+use rendering::DisplayList;
+use resource::BasicResourceManager;
+use state::StateBuffer;
+use util::{BufferFromTree, parent_indices};
+
+use super::TextData;
+
+/// Buffer of all the text nodes (static text and data bindings)
+/// of a view.
+pub struct TextBuffer {
+    text_data: BufferFromTree<TextData>,
+}
+
+impl Deref for TextBuffer {
+    type Target = [TextData];
+
+    fn deref<'a>(&'a self) -> &'a [TextData] {
+        self.text_data.deref()
+    }
+}
+
+impl TextBuffer {
+
+    pub fn new(view: &Node) -> TextBuffer {
+        let parents = parent_indices(view);
+        let siblings = inline_siblings(view);
+        let index = Cell::new(0);
+        let ancestors = |i: usize| {
+            let mut ancestors = Vec::new();
+            let mut current = parents[i];
+            while let Some(p) = current {
+                ancestors.push(p);
+                current = parents[p];
+            }
+            ancestors
+        };
+
+        let node_producer = |node: &Node| {
+            let i = index.get();
+            index.set(i + 1);
+            match node.node_type {
+                NodeType::Text(ref text) => {
+                    Some(TextData::new(text.clone(), false, ancestors(i), siblings[i]))
+                }
+                NodeType::Binding(_) |
+                NodeType::RepeatBinding(_) => {
+                    Some(TextData::new(String::new(), true, ancestors(i), siblings[i]))
+                }
+                _ => None
+            }
+        };
+
+        TextBuffer {
+            text_data: BufferFromTree::new_with_lookup_table(view, 0, node_producer)
+        }
+    }
+
+    /// Copy the current value of the data bindings in the text nodes.
+    /// Returns the global indices of the nodes whose text has changed.
+    pub fn update_from_bindings(&mut self, bindings: &DataBindingBuffer) -> Vec<usize> {
+
+        let mut changed = Vec::new();
+        let mut bindings = bindings.enumerate_bindings().peekable();
+
+        // Both buffers are sorted by global index.
+        for (&i, data) in self.text_data.enumerate_lookup_indices_mut().unwrap() {
+
+            if !data.is_binding {
+                continue;
+            }
+
+            while bindings.peek().map_or(false, |&(&j, _)| j < i) {
+                bindings.next();
+            }
+
+            if let Some(&(&j, binding)) = bindings.peek() {
+                if j == i {
+                    let text = binding.text();
+                    if text != data.text {
+                        data.text = text;
+                        changed.push(i);
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Resolve the font of each text node and measure its text.
+    /// Must be called before the layout is computed.
+    pub fn update_from_state<R>(
+        &mut self,
+        resource_manager: &R,
+        state_buffer: &StateBuffer,
+        units: &Units)
+        where R: BasicResourceManager
+    {
+        let opacities = state_buffer.opacities();
+
+        for (&i, data) in self.text_data.enumerate_lookup_indices_mut().unwrap() {
+            data.update_from_state(resource_manager, state_buffer, units, opacities[i]);
+        }
+    }
+
+    pub fn update_from_layout(&mut self, layout_data: &LayoutBuffer) {
+
+        for (&i, data) in self.text_data.enumerate_lookup_indices_mut().unwrap() {
+            // Safe for the same reasons as in the `RenderBuffer`.
+            let boxi = unsafe { layout_data.get_unchecked(i) };
+            data.update_coords(&boxi);
+        }
+    }
+
+    /// Same as `update_from_state`, for the text nodes
+    /// of the given subtrees only.
+    pub fn update_from_state_in<R>(
+        &mut self,
+        resource_manager: &R,
+        state_buffer: &StateBuffer,
+        units: &Units,
+        subtrees: &[Range<usize>])
+        where R: BasicResourceManager
+    {
+        let opacities = state_buffer.opacities();
+
+        for subtree in subtrees {
+            let nodes = self.text_data.enumerate_lookup_indices_in_mut(subtree.clone()).unwrap();
+            for (&i, data) in nodes {
+                data.update_from_state(resource_manager, state_buffer, units, opacities[i]);
+            }
+        }
+    }
+
+    /// Same as `update_from_layout`, for the text nodes
+    /// of the given subtrees only.
+    pub fn update_from_layout_in(&mut self, layout_data: &LayoutBuffer, subtrees: &[Range<usize>]) {
+
+        for subtree in subtrees {
+            let nodes = self.text_data.enumerate_lookup_indices_in_mut(subtree.clone()).unwrap();
+            for (&i, data) in nodes {
+                data.update_coords(&layout_data[i]);
+            }
+        }
+    }
+
+    pub fn push_commands(&self, list: &mut DisplayList) {
+
+        for data in self.text_data.iter() {
+            data.push_commands(list);
+        }
+    }
+
+    /// Push the commands of the node at the given global index.
+    pub fn push_commands_of(&self, index: usize, list: &mut DisplayList) {
+        if let Some(data) = self.text_data.get_by_global_index(index) {
+            data.push_commands(list);
+        }
+    }
+
+    /// Iterate over the text nodes along with their global index.
+    pub fn enumerate_lookup_indices<'a>(&'a self)
+        -> Zip<Iter<'a, usize>, Iter<'a, TextData>>
+    {
+        self.text_data.enumerate_lookup_indices().unwrap()
+    }
+
+    /// Iterate over the text nodes whose global index is in `range`.
+    pub fn enumerate_lookup_indices_in<'a>(&'a self, range: Range<usize>)
+        -> Zip<Iter<'a, usize>, Iter<'a, TextData>>
+    {
+        self.text_data.enumerate_lookup_indices_in(range).unwrap()
+    }
+}
+
+/// Returns, for each node of the tree, whether its previous
+/// and its next siblings are text nodes, drawn on the same line.
+fn inline_siblings(root: &Node) -> Vec<(bool, bool)> {
+
+    fn is_text(node: &Node) -> bool {
+        match node.node_type {
+            NodeType::Text(_) | NodeType::Binding(_) | NodeType::RepeatBinding(_) => true,
+            _ => false,
+        }
+    }
+
+    fn visit(node: &Node, siblings: (bool, bool), all: &mut Vec<(bool, bool)>) {
+        all.push(siblings);
+        let kids = &node.children;
+        for (i, kid) in kids.iter().enumerate() {
+            let before = i > 0 && is_text(&kids[i - 1]);
+            let after = kids.get(i + 1).map_or(false, is_text);
+            visit(kid, (before, after), all);
+        }
+    }
+
+    let mut all = Vec::new();
+    visit(root, (false, false), &mut all);
+    all
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::io::BufReader;
+    use markup;
+    use oil_parsers::EmptyErrorReporter;
+    use super::inline_siblings;
+
+    #[test]
+    fn texts_next_to_a_binding_should_have_inline_siblings() {
+        let lib = markup::parse(EmptyErrorReporter, BufReader::new(
+            "<view>\
+                <group>Welcome {{player.name}} to OIL!</group>\
+                <button>Play</button>\
+             </view>".as_bytes()));
+        let view = lib.views.get(markup::MAIN_VIEW_NAME).unwrap();
+
+        assert_eq!(inline_siblings(view), vec![
+            (false, false),
+            (false, false),
+            (false, true),
+            (true, true),
+            (true, false),
+            (false, false),
+            (false, false),
+        ]);
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::cmp;
+use std::char;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+use rusttype::{self, FontCollection, Scale, point};
+
+use layout::Rect;
+
+/// Minimal width of the glyph atlas in pixels.
+/// The height depends on the font size.
+const ATLAS_MIN_WIDTH: u32 = 256;
+
+/// Empty pixels kept around each glyph of the atlas,
+/// to avoid bleeding when the atlas is filtered.
+const ATLAS_PADDING: u32 = 1;
+
+/// Drawn in place of the characters that are not in the atlas.
+const REPLACEMENT_CHAR: char = '?';
+
+/// A font rasterized at a fixed size.
+///
+/// The glyphs of the printable ASCII and Latin-1 characters
+/// are rasterized once in an atlas, white with the coverage
+/// in the alpha channel. Backends draw text as a list of
+/// `GlyphQuad`s sampling that atlas.
+pub struct Font {
+    font: rusttype::Font<'static>,
+    scale: Scale,
+    glyphs: HashMap<char, GlyphInfo>,
+    atlas: RgbaImage,
+    ascent: f32,
+    line_height: f32,
+}
+
+/// A glyph to draw: the sub-rectangle `tex_coords` of the
+/// atlas (in normalized coordinates) is drawn over `bounds`.
+#[derive(Copy, Clone, Debug)]
+pub struct GlyphQuad {
+    pub bounds: Rect,
+    pub tex_coords: Rect,
+}
+
+struct GlyphInfo {
+    // Bitmap position relative to the pen on the baseline.
+    offset_x: f32,
+    offset_y: f32,
+    width: f32,
+    height: f32,
+    tex_coords: Rect,
+    advance: f32,
+}
+
+impl Font {
+
+    /// Load a TrueType font from a file.
+    pub fn from_file(path: &Path, width: f32, height: f32) -> io::Result<Font> {
+        let mut data = Vec::new();
+        try!(try!(File::open(path)).read_to_end(&mut data));
+        Font::from_bytes(data, width, height).ok_or(
+            io::Error::new(io::ErrorKind::InvalidData, "Invalid font file")
+        )
+    }
+
+    /// Load a TrueType font from memory. `width` and `height`
+    /// are the glyph size in pixels.
+    pub fn from_bytes(data: Vec<u8>, width: f32, height: f32) -> Option<Font> {
+        let font = match FontCollection::from_bytes(data).into_font() {
+            Some(font) => font,
+            None => return None,
+        };
+        let scale = Scale { x: width, y: height };
+        let v_metrics = font.v_metrics(scale);
+
+        let (glyphs, atlas) = {
+            let mut glyphs = HashMap::new();
+
+            // First pass: keep the glyphs that have a bitmap
+            // and find the widest one.
+            let mut to_rasterize = Vec::new();
+            let mut atlas_width = ATLAS_MIN_WIDTH;
+            for c in atlas_charset() {
+                let glyph = match font.glyph(c) {
+                    Some(glyph) => glyph.scaled(scale),
+                    None => continue,
+                };
+                let advance = glyph.h_metrics().advance_width;
+                let glyph = glyph.positioned(point(0.0, 0.0));
+
+                match glyph.pixel_bounding_box() {
+                    Some(bb) => {
+                        let w = (bb.max.x - bb.min.x) as u32;
+                        atlas_width = cmp::max(atlas_width, w + 2 * ATLAS_PADDING);
+                        to_rasterize.push((c, advance, glyph, bb));
+                    }
+                    // Whitespaces only move the pen.
+                    None => {
+                        glyphs.insert(c, GlyphInfo {
+                            offset_x: 0.0,
+                            offset_y: 0.0,
+                            width: 0.0,
+                            height: 0.0,
+                            tex_coords: Rect::default(),
+                            advance: advance,
+                        });
+                    }
+                }
+            }
+
+            // Second pass: pack the glyphs in rows.
+            let mut slots = Vec::with_capacity(to_rasterize.len());
+            let (mut x, mut y, mut row_height) = (ATLAS_PADDING, ATLAS_PADDING, 0);
+            for &(_, _, _, bb) in to_rasterize.iter() {
+                let w = (bb.max.x - bb.min.x) as u32;
+                let h = (bb.max.y - bb.min.y) as u32;
+                if x + w + ATLAS_PADDING > atlas_width {
+                    x = ATLAS_PADDING;
+                    y += row_height + ATLAS_PADDING;
+                    row_height = 0;
+                }
+                slots.push((x, y));
+                x += w + ATLAS_PADDING;
+                row_height = cmp::max(row_height, h);
+            }
+            let atlas_height = y + row_height + ATLAS_PADDING;
+
+            // Last pass: rasterize.
+            let mut atlas = RgbaImage::from_pixel(
+                atlas_width,
+                atlas_height,
+                Rgba([255, 255, 255, 0])
+            );
+            let (aw, ah) = (atlas_width as f32, atlas_height as f32);
+            for (&(c, advance, ref glyph, bb), &(sx, sy)) in to_rasterize.iter().zip(slots.iter()) {
+                glyph.draw(|gx, gy, v| {
+                    let alpha = (v.max(0.0).min(1.0) * 255.0).round() as u8;
+                    atlas.put_pixel(sx + gx, sy + gy, Rgba([255, 255, 255, alpha]));
+                });
+
+                let w = (bb.max.x - bb.min.x) as f32;
+                let h = (bb.max.y - bb.min.y) as f32;
+                glyphs.insert(c, GlyphInfo {
+                    offset_x: bb.min.x as f32,
+                    offset_y: bb.min.y as f32,
+                    width: w,
+                    height: h,
+                    tex_coords: Rect {
+                        x: sx as f32 / aw,
+                        y: sy as f32 / ah,
+                        width: w / aw,
+                        height: h / ah,
+                    },
+                    advance: advance,
+                });
+            }
+
+            (glyphs, atlas)
+        };
+
+        Some(Font {
+            font: font,
+            scale: scale,
+            glyphs: glyphs,
+            atlas: atlas,
+            ascent: v_metrics.ascent,
+            line_height: v_metrics.ascent - v_metrics.descent + v_metrics.line_gap,
+        })
+    }
+
+    /// Image containing all the glyphs of this font.
+    pub fn atlas(&self) -> &RgbaImage {
+        &self.atlas
+    }
+
+    /// Distance in pixels between two baselines.
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /// Returns the size in pixels of `text` written on a single line.
+    pub fn measure(&self, text: &str) -> (f32, f32) {
+        let mut width = 0f32;
+        self.walk_glyphs(text, |pen, glyph| {
+            width = pen + glyph.advance;
+        });
+        (width, self.line_height)
+    }
+
+    /// Returns the glyphs needed to draw `text` on a single line,
+    /// `(x, y)` being the top left corner of the line.
+    pub fn layout(&self, text: &str, x: f32, y: f32) -> Vec<GlyphQuad> {
+        let baseline = y + self.ascent;
+        let mut quads = Vec::with_capacity(text.len());
+        self.walk_glyphs(text, |pen, glyph| {
+            if glyph.width > 0.0 {
+                quads.push(GlyphQuad {
+                    // Glyphs are snapped to the pixel grid to keep them sharp.
+                    bounds: Rect {
+                        x: (x + pen + glyph.offset_x).round(),
+                        y: (baseline + glyph.offset_y).round(),
+                        width: glyph.width,
+                        height: glyph.height,
+                    },
+                    tex_coords: glyph.tex_coords,
+                });
+            }
+        });
+        quads
+    }
+
+    /// Call `f` with the pen position and the glyph
+    /// of each character of `text`.
+    fn walk_glyphs<F>(&self, text: &str, mut f: F)
+        where F: FnMut(f32, &GlyphInfo)
+    {
+        let mut pen = 0f32;
+        let mut previous = None;
+        for c in text.chars() {
+            let c = if self.glyphs.contains_key(&c) { c } else { REPLACEMENT_CHAR };
+            let glyph = match self.glyphs.get(&c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            if let Some(p) = previous {
+                pen += self.font.pair_kerning(self.scale, p, c);
+            }
+            f(pen, glyph);
+            pen += glyph.advance;
+            previous = Some(c);
+        }
+    }
+}
+
+/// Characters rasterized in the atlas:
+/// printable ASCII and Latin-1.
+fn atlas_charset() -> Vec<char> {
+    (32..127).chain(160..256).filter_map(char::from_u32).collect()
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use std::path::Path;
+    use super::Font;
+
+    fn load() -> Font {
+        Font::from_file(&Path::new("examples/DejaVuSansMono.ttf"), 16.0, 16.0).unwrap()
+    }
+
+    #[test]
+    fn measure_should_sum_the_advances() {
+        let font = load();
+        let (w1, h1) = font.measure("a");
+        let (w3, h3) = font.measure("abc");
+
+        assert!(w1 > 0.0);
+        // Monospace font: no kerning.
+        assert_eq!(w3, 3.0 * w1);
+        assert_eq!(h1, h3);
+        assert_eq!(font.measure(""), (0.0, font.line_height()));
+    }
+
+    #[test]
+    fn unknown_chars_should_be_replaced() {
+        let font = load();
+        assert_eq!(font.measure("\u{4e2d}"), font.measure("?"));
+    }
+
+    #[test]
+    fn layout_should_skip_whitespaces() {
+        let font = load();
+        let quads = font.layout("a b", 10.0, 20.0);
+
+        assert_eq!(quads.len(), 2);
+        assert!(quads[0].bounds.x >= 10.0);
+        assert!(quads[0].bounds.y >= 20.0);
+        assert!(quads[1].bounds.x > quads[0].bounds.x + quads[0].bounds.width);
+    }
+}
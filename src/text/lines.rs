@@ -0,0 +1,276 @@
+/// Horizontal alignment of the lines of a text
+/// (`text-align` property).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// How whitespaces and line breaks are handled
+/// (`white-space` property). Same meaning as in CSS.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WhiteSpace {
+    /// Whitespaces (newlines included) are collapsed, lines are wrapped.
+    Normal,
+    /// Whitespaces (newlines included) are collapsed, lines are not wrapped.
+    NoWrap,
+    /// Whitespaces are kept, lines are only broken at newlines.
+    Pre,
+    /// Whitespaces are collapsed but newlines are kept, lines are wrapped.
+    PreLine,
+}
+
+impl Default for TextAlign {
+    fn default() -> TextAlign {
+        TextAlign::Left
+    }
+}
+
+impl Default for WhiteSpace {
+    fn default() -> WhiteSpace {
+        WhiteSpace::Normal
+    }
+}
+
+/// Measured words of a text, used to break it
+/// into lines that fit in a given width.
+#[derive(Clone, Default, Debug)]
+pub struct TextMetrics {
+    // Words of each paragraph (separated by newlines).
+    paragraphs: Vec<Vec<Word>>,
+    // Collapsed spaces at the start and at the end of the text.
+    edge_spaces: (bool, bool),
+    // Edge spaces drawn, where the text touches an inline sibling.
+    kept_edge_spaces: (bool, bool),
+    space_width: f32,
+    line_height: f32,
+    wrap: bool,
+}
+
+/// A line of text once broken.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub text: String,
+    pub width: f32,
+}
+
+#[derive(Clone, Debug)]
+struct Word {
+    text: String,
+    width: f32,
+}
+
+impl TextMetrics {
+
+    /// Split `text` into words and measure them with `measure`.
+    pub fn new<F>(text: &str, white_space: WhiteSpace, line_height: f32, measure: F)
+        -> TextMetrics
+        where F: Fn(&str) -> f32
+    {
+        let collapse = |s: &str| {
+            let mut collapsed = String::with_capacity(s.len());
+            let mut previous_is_space = false;
+            for c in s.chars() {
+                if c.is_whitespace() {
+                    if !previous_is_space {
+                        collapsed.push(' ');
+                    }
+                    previous_is_space = true;
+                } else {
+                    collapsed.push(c);
+                    previous_is_space = false;
+                }
+            }
+            collapsed
+        };
+        // Collapsed spaces at the edges of the lines are dropped,
+        // they would shift centered and right-aligned lines.
+        // See `keep_edge_spaces` for the edges of the text.
+        let to_words = |s: &str| {
+            s.split(' ')
+                .filter(|w| !w.is_empty())
+                .map(|w| Word { text: w.to_string(), width: measure(w) })
+                .collect::<Vec<_>>()
+        };
+
+        let paragraphs = match white_space {
+            WhiteSpace::Normal | WhiteSpace::NoWrap => {
+                vec![to_words(&collapse(text))]
+            }
+            WhiteSpace::PreLine => {
+                text.split('\n').map(|p| to_words(&collapse(p))).collect()
+            }
+            // A single word per paragraph: spaces are never broken.
+            WhiteSpace::Pre => {
+                text.split('\n')
+                    .map(|p| vec![Word { text: p.to_string(), width: measure(p) }])
+                    .collect()
+            }
+        };
+
+        // Newlines break the lines in `pre-line`, they are not spaces.
+        let is_space = |c: char| match white_space {
+            WhiteSpace::Normal | WhiteSpace::NoWrap => c.is_whitespace(),
+            WhiteSpace::PreLine => c.is_whitespace() && c != '\n',
+            WhiteSpace::Pre => false,
+        };
+        let edge_spaces = (
+            text.chars().next().map_or(false, &is_space),
+            text.chars().next_back().map_or(false, &is_space)
+        );
+
+        TextMetrics {
+            paragraphs: paragraphs,
+            edge_spaces: edge_spaces,
+            kept_edge_spaces: (false, false),
+            space_width: measure(" "),
+            line_height: line_height,
+            wrap: white_space == WhiteSpace::Normal || white_space == WhiteSpace::PreLine,
+        }
+    }
+
+    #[inline]
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /// Keep the collapsed space at the start (`start`) or at the end
+    /// (`end`) of the text, where it touches an inline sibling: the
+    /// space separates the text from that sibling.
+    pub fn keep_edge_spaces(&mut self, start: bool, end: bool) {
+        self.kept_edge_spaces = (start, end);
+    }
+
+    /// Break the text into lines no wider than `max_width`.
+    /// A word wider than `max_width` gets a line of its own.
+    pub fn break_lines(&self, max_width: f32) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let space_before = self.edge_spaces.0 && self.kept_edge_spaces.0;
+        let space_after = self.edge_spaces.1 && self.kept_edge_spaces.1;
+        let last = self.paragraphs.len().saturating_sub(1);
+
+        for (p, paragraph) in self.paragraphs.iter().enumerate() {
+            let starts_with_space = p == 0 && space_before;
+            let mut text = if starts_with_space { " ".to_string() } else { String::new() };
+            let mut width = if starts_with_space { self.space_width } else { 0f32 };
+            let mut has_words = false;
+
+            for word in paragraph.iter() {
+                let new_width = if has_words {
+                    width + self.space_width + word.width
+                } else {
+                    width + word.width
+                };
+
+                if self.wrap && has_words && new_width > max_width {
+                    lines.push(Line { text: text, width: width });
+                    text = word.text.clone();
+                    width = word.width;
+                } else {
+                    if has_words {
+                        text.push(' ');
+                    }
+                    text.push_str(&word.text);
+                    width = new_width;
+                }
+                has_words = true;
+            }
+
+            // A text made of spaces only keeps a single one.
+            if p == last && space_after && (has_words || !starts_with_space) {
+                text.push(' ');
+                width += self.space_width;
+            }
+
+            // An empty paragraph still takes a line.
+            lines.push(Line { text: text, width: width });
+        }
+
+        lines
+    }
+
+    /// Size of the text broken into lines no wider than `max_width`.
+    pub fn size(&self, max_width: f32) -> (f32, f32) {
+        let lines = self.break_lines(max_width);
+        let width = lines.iter().fold(0f32, |w, l| w.max(l.width));
+        (width, lines.len() as f32 * self.line_height)
+    }
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+
+    use super::{TextMetrics, WhiteSpace, Line};
+
+    // Every char is 10px wide.
+    fn metrics(text: &str, white_space: WhiteSpace) -> TextMetrics {
+        TextMetrics::new(text, white_space, 20.0, |s| s.chars().count() as f32 * 10.0)
+    }
+
+    fn texts(m: &TextMetrics, max_width: f32) -> Vec<String> {
+        m.break_lines(max_width).into_iter().map(|l| l.text).collect()
+    }
+
+    #[test]
+    fn normal_should_collapse_and_wrap() {
+        let m = metrics("aaa  bbb\n   ccc", WhiteSpace::Normal);
+
+        assert_eq!(texts(&m, 1000.0), vec!["aaa bbb ccc"]);
+        assert_eq!(texts(&m, 70.0), vec!["aaa bbb", "ccc"]);
+        assert_eq!(m.size(70.0), (70.0, 40.0));
+    }
+
+    #[test]
+    fn long_words_should_get_their_own_line() {
+        let m = metrics("a bbbbbbbb c", WhiteSpace::Normal);
+
+        assert_eq!(texts(&m, 30.0), vec!["a", "bbbbbbbb", "c"]);
+    }
+
+    #[test]
+    fn spaces_at_line_edges_should_be_trimmed() {
+        let m = metrics("  aaa bbb ", WhiteSpace::Normal);
+        let pre_line = metrics(" aaa \n\n bbb", WhiteSpace::PreLine);
+
+        assert_eq!(m.break_lines(100.0), vec![Line { text: "aaa bbb".to_string(), width: 70.0 }]);
+        assert_eq!(texts(&m, 40.0), vec!["aaa", "bbb"]);
+        assert_eq!(texts(&pre_line, 100.0), vec!["aaa", "", "bbb"]);
+    }
+
+    #[test]
+    fn edge_spaces_should_be_kept_next_to_an_inline_sibling() {
+        let mut m = metrics(" aaa  bbb\n", WhiteSpace::Normal);
+        let mut spaces = metrics("   ", WhiteSpace::Normal);
+        let mut pre_line = metrics(" aaa\nbbb ", WhiteSpace::PreLine);
+        m.keep_edge_spaces(true, true);
+        spaces.keep_edge_spaces(true, true);
+        pre_line.keep_edge_spaces(false, true);
+
+        assert_eq!(m.break_lines(100.0), vec![Line { text: " aaa bbb ".to_string(), width: 90.0 }]);
+        // Spaces inside the text are still trimmed where it breaks.
+        assert_eq!(texts(&m, 50.0), vec![" aaa", "bbb "]);
+        assert_eq!(texts(&spaces, 100.0), vec![" "]);
+        assert_eq!(texts(&pre_line, 100.0), vec!["aaa", "bbb "]);
+    }
+
+    #[test]
+    fn nowrap_should_keep_a_single_line() {
+        let m = metrics("aaa bbb ccc", WhiteSpace::NoWrap);
+
+        assert_eq!(texts(&m, 30.0), vec!["aaa bbb ccc"]);
+    }
+
+    #[test]
+    fn newlines_should_break_in_pre_modes() {
+        let pre_line = metrics("aaa   bbb\nccc", WhiteSpace::PreLine);
+        let pre = metrics("aaa   bbb\nccc", WhiteSpace::Pre);
+
+        assert_eq!(texts(&pre_line, 1000.0), vec!["aaa bbb", "ccc"]);
+        assert_eq!(texts(&pre, 10.0), vec!["aaa   bbb", "ccc"]);
+    }
+}
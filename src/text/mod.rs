@@ -0,0 +1,175 @@
+// ======================================== //
+//                INTERFACE                 //
+// ======================================== //
+
+pub use self::buffer::TextBuffer;
+pub use self::font::{Font, GlyphQuad};
+pub use self::lines::{TextMetrics, TextAlign, WhiteSpace};
+
+mod buffer;
+mod font;
+mod lines;
+
+// ======================================== //
+//                INTERNALS                 //
+// ======================================== //
+
+use layout::{LayoutBox, Rect, Units};
+use rendering::{DisplayList, DrawCommand, TextRun};
+use resource::{BasicResourceManager, ResourceId};
+use state::{StateBuffer, StateData};
+use oil_shared::properties::PropertyName;
+
+/// Color of the text when no ancestor defines one, black.
+const DEFAULT_TEXT_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// Text content of a `Text` or `Binding` node.
+pub struct TextData {
+    text: String,
+    is_binding: bool,
+    // Global indices of the ancestors, closest first.
+    ancestors: Vec<usize>,
+    // Whether the previous and the next siblings are texts as well.
+    inline_siblings: (bool, bool),
+    // Resolved from the style
+    font: Option<ResourceId>,
+    font_line_height: f32,
+    color: [f32; 4],
+    align: TextAlign,
+    metrics: TextMetrics,
+    // Lines to draw along with their bounds.
+    lines: Vec<(String, Rect)>,
+}
+
+impl TextData {
+
+    fn new(text: String, is_binding: bool, ancestors: Vec<usize>, inline_siblings: (bool, bool))
+        -> TextData
+    {
+        TextData {
+            text: text,
+            is_binding: is_binding,
+            ancestors: ancestors,
+            inline_siblings: inline_siblings,
+            font: None,
+            font_line_height: 0.0,
+            color: DEFAULT_TEXT_COLOR,
+            align: TextAlign::default(),
+            metrics: TextMetrics::default(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Measured words of the text, used by the layout
+    /// to break it into lines. A text without font
+    /// has no size.
+    pub fn metrics(&self) -> &TextMetrics {
+        &self.metrics
+    }
+
+    /// Lines to draw along with their bounds.
+    pub fn lines(&self) -> &[(String, Rect)] {
+        &self.lines
+    }
+
+    /// Returns true if the lines are aligned on the left side.
+    pub fn is_left_aligned(&self) -> bool {
+        self.align == TextAlign::Left
+    }
+
+    /// All the text properties are inherited from
+    /// the closest ancestor that defines them.
+    fn update_from_state<R>(
+        &mut self,
+        resource_manager: &R,
+        state_buffer: &StateBuffer,
+        units: &Units,
+        opacity: f32)
+        where R: BasicResourceManager
+    {
+        let ancestors = &self.ancestors;
+        self.font = inherit(ancestors, state_buffer, |s| s.get_font()).map(|f| f.font);
+        self.color = inherit(ancestors, state_buffer, |s| s.get_color())
+            .map(|c| c.to_f32())
+            .unwrap_or(DEFAULT_TEXT_COLOR);
+        self.color[3] *= opacity;
+        self.align = inherit(ancestors, state_buffer, |s| s.get_text_align())
+            .unwrap_or(TextAlign::default());
+        let white_space = inherit(ancestors, state_buffer, |s| s.get_white_space())
+            .unwrap_or(WhiteSpace::default());
+        let line_height = inherit(ancestors, state_buffer,
+            |s| s.length_prop_as_opt(PropertyName::LINE_HEIGHT));
+
+        self.metrics = match self.font {
+            Some(font) => {
+                let font_line_height = resource_manager.measure_text(font, "").1;
+                self.font_line_height = font_line_height;
+                TextMetrics::new(
+                    &self.text,
+                    white_space,
+                    // A percentage is relative to the line height of the font.
+                    line_height.map_or(font_line_height, |l| l.resolve(units, font_line_height)),
+                    |s| resource_manager.measure_text(font, s).0
+                )
+            }
+            None => TextMetrics::default(),
+        };
+        // The spaces separating this text from its siblings are kept.
+        let (before, after) = self.inline_siblings;
+        self.metrics.keep_edge_spaces(before, after);
+    }
+
+    /// Break the text into lines using the final width of the node.
+    fn update_coords(&mut self, lb: &LayoutBox) {
+        let x = lb.dim().content.x + lb.dim().margin.left;
+        let y = lb.dim().content.y + lb.dim().margin.top;
+        let width = lb.dim().content.width;
+        let align = self.align;
+        let font_line_height = self.font_line_height;
+        let line_height = self.metrics.line_height();
+        // Lines are centered vertically when the line height
+        // is greater than the one of the font.
+        let leading = (line_height - font_line_height) / 2.0;
+
+        let lines = self.metrics.break_lines(width)
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let offset = match align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => (width - line.width) / 2.0,
+                    TextAlign::Right => width - line.width,
+                };
+                (line.text, Rect {
+                    x: x + offset,
+                    y: y + i as f32 * line_height + leading,
+                    width: line.width,
+                    height: font_line_height,
+                })
+            })
+            .collect();
+        self.lines = lines;
+    }
+
+    fn push_commands(&self, list: &mut DisplayList) {
+        if let Some(font) = self.font {
+            for &(ref text, bounds) in self.lines.iter() {
+                if !text.is_empty() {
+                    list.push(DrawCommand::Text(TextRun {
+                        text: text.clone(),
+                        bounds: bounds,
+                        font: font,
+                        color: self.color,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the first value found in the state of the ancestors.
+fn inherit<T, F>(ancestors: &[usize], state_buffer: &StateBuffer, f: F) -> Option<T>
+    where F: Fn(&StateData) -> Option<T>
+{
+    ancestors.iter().filter_map(|&i| f(&state_buffer[i])).next()
+}
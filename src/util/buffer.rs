@@ -1,9 +1,9 @@
 use std::iter::Zip;
 use std::slice::{Iter, IterMut};
-use std::ops::RangeFrom;
+use std::ops::{Range, RangeFrom};
 use std::ops::{Deref, DerefMut};
 
-use super::HasChildren;
+use super::{HasChildren, lookup_range};
 
 pub struct BufferFromTree<T>{
     buffer: Box<[T]>,
@@ -118,6 +118,41 @@ impl<T> BufferFromTree<T> {
         }
     }
 
+    /// Same as `enumerate_lookup_indices`, restricted to the
+    /// nodes whose global index is in `range`.
+    pub fn enumerate_lookup_indices_in<'a>(&'a self, range: Range<usize>)
+        -> Option<Zip<Iter<usize>, Iter<'a, T>>>
+    {
+        if let Some(ref tb) = self.lookup_indices {
+            let r = lookup_range(tb, range);
+            Some(tb[r.clone()].iter().zip(self.buffer[r].iter()))
+        } else {
+            None
+        }
+    }
+
+    /// Same as `enumerate_lookup_indices_mut`, restricted to the
+    /// nodes whose global index is in `range`.
+    pub fn enumerate_lookup_indices_in_mut<'a>(&'a mut self, range: Range<usize>)
+        -> Option<Zip<Iter<usize>, IterMut<'a, T>>>
+    {
+        if let Some(ref tb) = self.lookup_indices {
+            let r = lookup_range(tb, range);
+            Some(tb[r.clone()].iter().zip(self.buffer[r].iter_mut()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the data of the node at the given index
+    /// in the original tree, if it has any.
+    pub fn get_by_global_index(&self, index: usize) -> Option<&T> {
+        match self.lookup_indices {
+            Some(ref tb) => tb.binary_search(&index).ok().map(|i| &self.buffer[i]),
+            None => self.buffer.get(index),
+        }
+    }
+
     pub fn enumerate<'a>(&'a self)
         -> Zip<RangeFrom<usize>, Iter<'a, T>>
     {
@@ -2,10 +2,10 @@ use std::ops::Deref;
 use std::ops::DerefMut;
 use std::iter::Zip;
 use std::slice::{Iter, IterMut};
-use std::ops::RangeFrom;
+use std::ops::{Range, RangeFrom};
 use std::mem;
 
-use util::HasChildren;
+use util::{HasChildren, lookup_range};
 use super::TreeNode;
 use super::FlatTreeIter;
 use super::FlatTreeIterMut;
@@ -112,6 +112,19 @@ impl<T> FlatTree<T> {
         }
     }
 
+    /// Same as `enumerate_lookup_indices_mut`, restricted to the
+    /// nodes whose global index is in `range`.
+    pub fn enumerate_lookup_indices_in_mut<'a>(&'a mut self, range: Range<usize>)
+        -> Option<Zip<Iter<usize>, IterMut<'a, TreeNode<T>>>>
+    {
+        if let Some(ref tb) = self.lookup_indices {
+            let r = lookup_range(tb, range);
+            Some(tb[r.clone()].iter().zip(self.buffer[r].iter_mut()))
+        } else {
+            None
+        }
+    }
+
     pub fn enumerate_mut<'a>(& 'a mut self)
         -> Zip<RangeFrom<usize>, IterMut<'a, TreeNode<T>>>
     {
@@ -1,5 +1,6 @@
 use std::cmp::Ord;
 use std::cmp::Ordering;
+use std::ops::Range;
 
 pub mod flat_tree;
 
@@ -33,3 +34,111 @@ pub trait HasChildren {
 
     fn children<'a>(&'a self) -> &'a [Self];
 }
+
+/// Returns the global index of the parent of each node of the tree.
+pub fn parent_indices<N: HasChildren>(root: &N) -> Vec<Option<usize>> {
+
+    fn visit<N: HasChildren>(node: &N, parent: Option<usize>, parents: &mut Vec<Option<usize>>) {
+        let index = parents.len();
+        parents.push(parent);
+        for kid in node.children().iter() {
+            visit(kid, Some(index), parents);
+        }
+    }
+
+    let mut parents = Vec::new();
+    visit(root, None, &mut parents);
+    parents
+}
+
+/// Returns, for each node, the global index following its last
+/// descendant: a node and its descendants are at `i..ends[i]`.
+pub fn subtree_ends(parents: &[Option<usize>]) -> Vec<usize> {
+
+    let mut ends: Vec<usize> = (1..parents.len() + 1).collect();
+
+    // Children always come after their parent.
+    for i in (0..parents.len()).rev() {
+        if let Some(p) = parents[i] {
+            ends[p] = ends[p].max(ends[i]);
+        }
+    }
+
+    ends
+}
+
+/// Sort the given subtrees and drop the ones contained in another.
+/// Two subtrees are either disjoint or one contains the other.
+pub fn merge_subtrees(mut subtrees: Vec<Range<usize>>) -> Vec<Range<usize>> {
+
+    // The largest first when they start at the same index.
+    subtrees.sort_by(|a, b| (a.start, b.end).cmp(&(b.start, a.end)));
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(subtrees.len());
+
+    for subtree in subtrees {
+        if merged.last().map_or(true, |last| subtree.start >= last.end) {
+            merged.push(subtree);
+        }
+    }
+
+    merged
+}
+
+/// Positions in a sorted lookup table of the global indices in `range`.
+pub fn lookup_range(lookup_indices: &[usize], range: Range<usize>) -> Range<usize> {
+
+    let start = match lookup_indices.binary_search(&range.start) {
+        Ok(i) | Err(i) => i,
+    };
+    let end = match lookup_indices.binary_search(&range.end) {
+        Ok(i) | Err(i) => i,
+    };
+
+    start..end
+}
+
+// ======================================== //
+//                   TESTS                  //
+// ======================================== //
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn subtree_ends_should_follow_the_last_descendant() {
+        // 0 ─┬─ 1 ─┬─ 2
+        //    │     └─ 3
+        //    └─ 4 ─── 5
+        let parents = [None, Some(0), Some(1), Some(1), Some(0), Some(4)];
+
+        assert_eq!(subtree_ends(&parents), vec![6, 4, 3, 4, 6, 6]);
+    }
+
+    #[test]
+    fn subtrees_contained_in_another_should_be_merged() {
+        assert_eq!(merge_subtrees(vec![2..5, 0..10, 3..4]), vec![0..10]);
+        // Same start, the largest is kept.
+        assert_eq!(merge_subtrees(vec![3..4, 3..8, 9..10]), vec![3..8, 9..10]);
+        assert_eq!(merge_subtrees(vec![2..5, 2..5]), vec![2..5]);
+    }
+
+    #[test]
+    fn adjacent_subtrees_should_be_kept_apart() {
+        assert_eq!(merge_subtrees(vec![5..8, 2..5, 8..9]), vec![2..5, 5..8, 8..9]);
+    }
+
+    #[test]
+    fn lookup_range_should_skip_the_missing_indices() {
+        let lookup = [1, 4, 7, 9];
+
+        assert_eq!(lookup_range(&lookup, 0..3), 0..1);
+        assert_eq!(lookup_range(&lookup, 4..9), 1..3);
+        assert_eq!(lookup_range(&lookup, 7..8), 2..3);
+        assert_eq!(lookup_range(&lookup, 0..10), 0..4);
+        // Nothing in the table.
+        assert_eq!(lookup_range(&lookup, 2..4), 1..1);
+        assert_eq!(lookup_range(&lookup, 10..12), 4..4);
+    }
+}
@@ -0,0 +1,37 @@
+extern crate image;
+extern crate oil;
+
+mod util;
+
+use oil::Viewport;
+
+const VIEWPORT: Viewport = Viewport { width: 800.0, height: 600.0 };
+
+fn check_example(name: &str) {
+    let output = util::render_fixture(
+        &format!("./examples/{}.markup", name),
+        &format!("./examples/{}.deps", name),
+        &format!("./examples/{}.style", name),
+        VIEWPORT
+    );
+
+    util::assert_matches_reference(name, &output);
+}
+
+#[test]
+#[ignore] // No reference image yet, see tests/references/README.md
+fn menu() {
+    check_example("menu");
+}
+
+#[test]
+#[ignore] // No reference image yet, see tests/references/README.md
+fn list() {
+    check_example("list");
+}
+
+#[test]
+#[ignore] // No reference image yet, see tests/references/README.md
+fn shop_inventory() {
+    check_example("shop_inventory");
+}
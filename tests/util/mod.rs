@@ -0,0 +1,131 @@
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{self, Rgba, RgbaImage};
+use oil::RenderBackbend;
+use oil;
+
+/// Maximum difference allowed on a single color channel
+/// before a pixel is considered different from the reference.
+pub const CHANNEL_TOLERANCE: u8 = 2;
+
+/// Render a `.markup`/`.deps`/`.style` triple into an image with
+/// the software renderer.
+pub fn render_fixture(
+    markup_path: &str,
+    deps_path: &str,
+    style_path: &str,
+    vp: oil::Viewport)
+    -> RgbaImage
+{
+    let mut library = oil::markup::parse_file(oil::StdOutErrorReporter, markup_path);
+
+    library.resolve_templates();
+
+    let defs = oil::deps::parse_file(oil::StdOutErrorReporter, deps_path);
+
+    let mut resource_manager = oil::resource::create_software_manager();
+
+    let stylesheet = oil::style::parse_file(
+        oil::StdOutErrorReporter, style_path, &defs, &mut resource_manager);
+
+    let mut renderer = oil::rendering::backend::SoftwareRenderer::new();
+    let mut router = oil::Router::from_library_and_stylesheet(
+        &resource_manager,
+        library,
+        &stylesheet
+    );
+    let mut data_binder_context = oil::DefaultContextManager::default();
+
+    router.update(&resource_manager, vp, &mut data_binder_context);
+
+    let mut frame = renderer.prepare_frame(vp);
+    router.render_views(&renderer, &mut frame, &resource_manager);
+    frame
+}
+
+/// Compare `actual` with the reference image `tests/references/<name>.png`.
+///
+/// When the `OIL_UPDATE_REFERENCES` environment variable is set to `1`,
+/// the reference is (re)written instead. A missing reference fails.
+/// On mismatch, the output and a diff image are written
+/// in `target/references/` and the test fails.
+pub fn assert_matches_reference(name: &str, actual: &RgbaImage) {
+    let reference_path = Path::new("tests/references").join(format!("{}.png", name));
+
+    if env::var("OIL_UPDATE_REFERENCES").map(|v| v == "1").unwrap_or(false) {
+        save(&reference_path, actual);
+        return;
+    }
+
+    if !reference_path.exists() {
+        let out = write_output(name, actual, None);
+        panic!("{}: no reference image at {} (see {}, run with \
+                OIL_UPDATE_REFERENCES=1 to write it)",
+            name, reference_path.display(), out.display());
+    }
+
+    let expected = image::open(&reference_path).unwrap().to_rgba();
+
+    if expected.dimensions() != actual.dimensions() {
+        let out = write_output(name, actual, None);
+        panic!("{}: expected dimensions {:?}, got {:?} (see {})",
+            name, expected.dimensions(), actual.dimensions(), out.display());
+    }
+
+    let (diff, count) = diff_images(&expected, actual);
+    if count > 0 {
+        let out = write_output(name, actual, Some(&diff));
+        panic!("{}: {} pixel(s) differ from the reference (see {})",
+            name, count, out.display());
+    }
+}
+
+/// Returns an image highlighting the pixels that differ
+/// along with the number of such pixels.
+pub fn diff_images(expected: &RgbaImage, actual: &RgbaImage) -> (RgbaImage, usize) {
+    let (w, h) = expected.dimensions();
+    let mut diff = RgbaImage::new(w, h);
+    let mut count = 0;
+
+    for (x, y, e) in expected.enumerate_pixels() {
+        let a = actual.get_pixel(x, y);
+        let differs = e.data.iter().zip(a.data.iter()).any(|(&c1, &c2)| {
+            (c1 as i16 - c2 as i16).abs() > CHANNEL_TOLERANCE as i16
+        });
+
+        if differs {
+            count += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            // Keep a faded version of the expected image for context.
+            diff.put_pixel(x, y, Rgba([e.data[0] / 4, e.data[1] / 4, e.data[2] / 4, 255]));
+        }
+    }
+
+    (diff, count)
+}
+
+fn write_output(name: &str, actual: &RgbaImage, diff: Option<&RgbaImage>) -> PathBuf {
+    let dir = Path::new("target/references");
+    fs::create_dir_all(dir).unwrap();
+
+    let actual_path = dir.join(format!("{}.png", name));
+    save(&actual_path, actual);
+
+    if let Some(diff) = diff {
+        save(&dir.join(format!("{}.diff.png", name)), diff);
+    }
+
+    actual_path
+}
+
+fn save(path: &Path, img: &RgbaImage) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let (w, h) = img.dimensions();
+    image::save_buffer(path, &img[..], w, h, image::ColorType::RGBA(8)).unwrap();
+}